@@ -1,19 +1,35 @@
 use core::fmt;
+use std::io::Write;
 use std::str::FromStr;
 
 use super::verify_file;
-use crate::{get_reader, process_decode, process_encode, CmdExecutor};
+use crate::{
+    default_padding, get_content, get_reader, get_writer, process_datauri_decode,
+    process_datauri_encode, process_decode, process_decode_auto, process_encode, CmdExecutor,
+};
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
 pub enum Base64SubCommand {
-    #[command(name = "encode", about = "Encode input to base64")]
+    #[command(
+        name = "encode",
+        about = "Encode input to base64, base32, base58, hex or ascii85"
+    )]
     Encode(Base64EncodeOpts),
 
-    #[command(name = "decode", about = "Decode base64 string")]
+    #[command(
+        name = "decode",
+        about = "Decode base64, base32, base58, hex or ascii85 input"
+    )]
     Decode(Base64DecodeOpts),
+
+    #[command(
+        name = "datauri",
+        about = "Generate a data URI from a file, or extract one"
+    )]
+    DataUri(Base64DataUriOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -21,8 +37,28 @@ pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    #[arg(short, long, value_parser = parse_codec, default_value = "base64")]
+    pub codec: Codec,
+
+    // base64 的字母表，standard 或者 url safe
     #[arg(short, long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+
+    // 是否输出 padding，不指定时标准 base64 和 base32 带 padding，url safe 不带 padding
+    #[arg(long)]
+    pub padding: Option<bool>,
+
+    // 输出文件，默认输出到 stdout
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 每 N 列换行，MIME 使用 76 列，PEM 使用 64 列
+    #[arg(short, long)]
+    pub wrap: Option<usize>,
+
+    // 输出 -----BEGIN LABEL----- / -----END LABEL----- 格式，默认 64 列换行
+    #[arg(long)]
+    pub armor: Option<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -30,20 +66,68 @@ pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    #[arg(short, long, value_parser = parse_codec, default_value = "base64")]
+    pub codec: Codec,
+
+    // auto 会根据字母表和 padding 自动识别格式
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+
+    // 输入是否带 padding，默认值和 encode 相同
+    #[arg(long)]
+    pub padding: Option<bool>,
+
+    // 解码后的原始字节写入的文件，默认输出到 stdout
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 校验 armor 的 label，armor 本身总是会被自动去掉
+    #[arg(long)]
+    pub armor: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Parser)]
+pub struct Base64DataUriOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 指定 MIME 类型，不指定则根据文件内容和扩展名判断
+    #[arg(long)]
+    pub mime: Option<String>,
+
+    // 输入是一个 data URI，把其中的 payload 解码后写入 output
+    #[arg(long, default_value_t = false)]
+    pub from_datauri: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Base64Format {
     Standard,
     UrlSafe,
+    // 只用于解码，根据输入自动识别
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    Base64,
+    Base32,
+    Base58,
+    Hex,
+    Ascii85,
 }
 
 fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
     format.parse()
 }
 
+fn parse_codec(codec: &str) -> Result<Codec, anyhow::Error> {
+    codec.parse()
+}
+
 impl FromStr for Base64Format {
     type Err = anyhow::Error;
 
@@ -51,6 +135,7 @@ impl FromStr for Base64Format {
         match s {
             "standard" => Ok(Base64Format::Standard),
             "url" => Ok(Base64Format::UrlSafe),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid base64 format: {}", s)),
         }
     }
@@ -61,6 +146,7 @@ impl From<Base64Format> for &'static str {
         match format {
             Base64Format::Standard => "standard",
             Base64Format::UrlSafe => "url",
+            Base64Format::Auto => "auto",
         }
     }
 }
@@ -71,12 +157,61 @@ impl fmt::Display for Base64Format {
     }
 }
 
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(Codec::Base64),
+            "base32" => Ok(Codec::Base32),
+            "base58" => Ok(Codec::Base58),
+            "hex" => Ok(Codec::Hex),
+            "ascii85" => Ok(Codec::Ascii85),
+            _ => Err(anyhow::anyhow!("Invalid codec: {}", s)),
+        }
+    }
+}
+
+impl From<Codec> for &'static str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Base64 => "base64",
+            Codec::Base32 => "base32",
+            Codec::Base58 => "base58",
+            Codec::Hex => "hex",
+            Codec::Ascii85 => "ascii85",
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
 impl CmdExecutor for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
 
-        let ret = process_encode(&mut reader, self.format)?;
-        println!("{}", ret);
+        let wrap = match (self.wrap, &self.armor) {
+            (Some(wrap), _) => wrap,
+            (None, Some(_)) => 64,
+            (None, None) => 0,
+        };
+        let padding = self
+            .padding
+            .unwrap_or_else(|| default_padding(self.codec, self.format));
+        process_encode(
+            &mut reader,
+            &mut writer,
+            self.codec,
+            self.format,
+            padding,
+            wrap,
+            self.armor.as_deref(),
+        )?;
         Ok(())
     }
 }
@@ -84,8 +219,46 @@ impl CmdExecutor for Base64EncodeOpts {
 impl CmdExecutor for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
-        let ret = process_decode(&mut reader, self.format)?;
-        println!("{}", ret);
+        let mut writer = get_writer(&self.output)?;
+
+        if let (Codec::Base64, Base64Format::Auto) = (self.codec, self.format) {
+            let variant = process_decode_auto(&mut reader, &mut writer, self.armor.as_deref())?;
+            // stdout 可能是解码后的二进制数据，识别结果输出到 stderr
+            eprintln!("Detected base64 variant: {}", variant);
+            return Ok(());
+        }
+
+        let padding = self
+            .padding
+            .unwrap_or_else(|| default_padding(self.codec, self.format));
+        process_decode(
+            &mut reader,
+            &mut writer,
+            self.codec,
+            self.format,
+            padding,
+            self.armor.as_deref(),
+        )?;
+        Ok(())
+    }
+}
+
+impl CmdExecutor for Base64DataUriOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let content = get_content(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+
+        if self.from_datauri {
+            let uri = String::from_utf8(content)?;
+            let mime = process_datauri_decode(&uri, &mut writer)?;
+            eprintln!("MIME type: {}", mime);
+            return Ok(());
+        }
+
+        let path = (self.input != "-").then_some(self.input.as_str());
+        let uri = process_datauri_encode(&content, path, self.mime.as_deref());
+        writeln!(writer, "{}", uri)?;
+        writer.flush()?;
         Ok(())
     }
 }
@@ -8,6 +8,7 @@ use std::str::FromStr;
 pub enum OutputFormat {
     Json,
     Yaml,
+    Csv,
 }
 
 /// 单个子命令的参数定义在一个 struct 中
@@ -40,7 +41,7 @@ pub struct CsvOpts {
     pub header: bool,
 }
 
-fn parse_format(format: &str) -> anyhow::Result<OutputFormat, anyhow::Error> {
+pub(crate) fn parse_format(format: &str) -> anyhow::Result<OutputFormat, anyhow::Error> {
     // pub fn parse<F>(&self) -> Result<F, F::Err>
     // 根据返回值类型推导需要 parse 的类型
     // FromStr::from_str(self) 里面会调用这个方法，最终调用到 from_str 方法
@@ -53,6 +54,7 @@ impl From<OutputFormat> for &'static str {
         match format {
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
         }
     }
 }
@@ -67,6 +69,7 @@ impl FromStr for OutputFormat {
         match s {
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
             _ => Err(anyhow::anyhow!("Invalid format: {}", s)),
         }
     }
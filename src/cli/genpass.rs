@@ -1,9 +1,19 @@
-use crate::CmdExecutor;
+use super::{parse_format, verify_file};
+use crate::{
+    get_reader, process_genpass_batch, read_accounts, serialize_records, BreachDb, CmdExecutor,
+    OutputFormat,
+};
 use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::io::{BufRead, BufReader};
 use zxcvbn::zxcvbn;
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct GenPassOpts {
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+
     #[arg(short, long, default_value_t = 16)]
     pub length: u8,
 
@@ -19,22 +29,130 @@ pub struct GenPassOpts {
 
     #[arg(long, default_value_t = true)]
     pub symbol: bool,
+
+    // 批量生成的数量，大于 1 时输出结构化的记录
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    // 批量输出的格式，指定后即使 count 为 1 也输出结构化的记录
+    #[arg(short, long, value_parser = parse_format)]
+    pub format: Option<OutputFormat>,
+
+    // 从 csv 文件中读取 account 名称，每个 account 生成一个密码，数量由 account 决定
+    #[arg(long, value_parser = verify_file, conflicts_with = "count")]
+    pub accounts: Option<String>,
+
+    #[arg(long, default_value = "account")]
+    pub account_column: String,
+
+    // 批量输出的文件，不指定则输出到 stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    // 本地的 HIBP 泄露库，生成的密码如果在其中出现则重新生成
+    #[arg(long, value_parser = verify_file)]
+    pub breach_db: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum GenPassSubCommand {
+    #[command(about = "Check passwords against a local Have I Been Pwned hash file")]
+    Check(GenPassCheckOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    // 待检查的密码，每行一个
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    // SHA-1 ordered-by-hash 格式的 HIBP 文件
+    #[arg(long, value_parser = verify_file)]
+    pub breach_db: String,
 }
 
 impl CmdExecutor for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_genpass(
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        if self.count > 1 || self.format.is_some() || self.accounts.is_some() {
+            return self.execute_batch();
+        }
+
+        let generate = || {
+            crate::process_genpass(
+                self.length,
+                self.uppercase,
+                self.lowercase,
+                self.number,
+                self.symbol,
+            )
+        };
+        let ret = match &self.breach_db {
+            Some(path) => BreachDb::open(path)?.reject_breached(generate)?,
+            None => generate()?,
+        };
+        println!("{}", ret);
+
+        // output password strength in stderr
+        let estimate = zxcvbn(&ret, &[])?;
+        eprintln!("Password strength: {}", estimate.score());
+        Ok(())
+    }
+}
+
+impl GenPassOpts {
+    fn execute_batch(self) -> anyhow::Result<()> {
+        let accounts = match &self.accounts {
+            Some(input) => read_accounts(input, &self.account_column)?
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => vec![None; self.count],
+        };
+        let mut breach_db = self.breach_db.as_ref().map(BreachDb::open).transpose()?;
+
+        let records = process_genpass_batch(
+            accounts,
             self.length,
             self.uppercase,
             self.lowercase,
             self.number,
             self.symbol,
+            breach_db.as_mut(),
         )?;
-        println!("{}", ret);
 
-        // output password strength in stderr
-        let estimate = zxcvbn(&ret, &[])?;
-        eprintln!("Password strength: {}", estimate.score());
+        let content = serialize_records(&records, self.format.unwrap_or(OutputFormat::Json))?;
+        match self.output {
+            Some(output) => std::fs::write(output, content)?,
+            None => println!("{}", content.trim_end()),
+        }
+
+        Ok(())
+    }
+}
+
+impl CmdExecutor for GenPassCheckOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut db = BreachDb::open(&self.breach_db)?;
+        let reader = BufReader::new(get_reader(&self.input)?);
+
+        for line in reader.lines() {
+            let line = line?;
+            let password = line.trim_end_matches('\r');
+            if password.is_empty() {
+                continue;
+            }
+
+            match db.count(password)? {
+                0 => println!("✓ {} not found in breach database", password),
+                n => println!("⚠ {} found {} times in breach database", password, n),
+            }
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,148 @@
+use super::verify_file;
+use crate::{
+    format_checksum_line, get_content, get_reader, process_hash, process_hash_check,
+    ChecksumStatus, CmdExecutor,
+};
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    // 要计算的文件，- 表示 stdin；--check 时是 checksum 列表文件
+    #[arg(value_parser = verify_file, default_value = "-")]
+    pub files: Vec<String>,
+
+    #[arg(short, long, default_value = "sha256", value_parser = parse_hash_algorithm)]
+    pub algorithm: HashAlgorithm,
+
+    // 读取 sha256sum 格式的 checksum 列表并校验每个文件
+    #[arg(short, long, default_value_t = false)]
+    pub check: bool,
+
+    // --check 时不输出校验通过的文件
+    #[arg(short, long, default_value_t = false, requires = "check")]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+    Sha512,
+    Sha1,
+}
+
+fn parse_hash_algorithm(algorithm: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            _ => Err(anyhow::anyhow!("Invalid hash algorithm: {}", s)),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Sha1 => "sha1",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if !self.check {
+            for file in &self.files {
+                let mut reader = get_reader(file)?;
+                let hash = process_hash(&mut reader, self.algorithm)?;
+                println!("{}", format_checksum_line(&hash, file));
+            }
+            return Ok(());
+        }
+
+        let (mut failed, mut unreadable, mut malformed, mut checked) = (0, 0, 0, 0);
+        for file in &self.files {
+            let content = String::from_utf8(get_content(file)?)?;
+            let (checks, invalid) = process_hash_check(&content, self.algorithm);
+            malformed += invalid;
+            checked += checks.len();
+            for check in checks {
+                match check.status {
+                    ChecksumStatus::Ok if self.quiet => {}
+                    ChecksumStatus::Ok => println!("{}: OK", check.path),
+                    ChecksumStatus::Failed => {
+                        failed += 1;
+                        println!("{}: FAILED", check.path);
+                    }
+                    ChecksumStatus::Unreadable => {
+                        unreadable += 1;
+                        println!("{}: FAILED open or read", check.path);
+                    }
+                }
+            }
+        }
+
+        // 和 coreutils 一样，警告输出到 stderr，有任何失败时返回非 0
+        if malformed > 0 {
+            eprintln!(
+                "WARNING: {} {} improperly formatted",
+                malformed,
+                plural(malformed, "line is", "lines are")
+            );
+        }
+        if unreadable > 0 {
+            eprintln!(
+                "WARNING: {} listed {} could not be read",
+                unreadable,
+                plural(unreadable, "file", "files")
+            );
+        }
+        if checked == 0 {
+            anyhow::bail!(
+                "No properly formatted {} checksum lines found",
+                self.algorithm
+            );
+        }
+        if failed > 0 {
+            anyhow::bail!(
+                "{} computed {} did NOT match",
+                failed,
+                plural(failed, "checksum", "checksums")
+            );
+        }
+        if unreadable > 0 {
+            anyhow::bail!(
+                "{} listed {} could not be read",
+                unreadable,
+                plural(unreadable, "file", "files")
+            );
+        }
+        Ok(())
+    }
+}
+
+fn plural(n: usize, one: &'static str, many: &'static str) -> &'static str {
+    if n == 1 {
+        one
+    } else {
+        many
+    }
+}
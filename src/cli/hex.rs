@@ -0,0 +1,61 @@
+use super::verify_file;
+use crate::{get_reader, get_writer, process_hex_dump, process_hex_reverse, CmdExecutor};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum HexSubCommand {
+    #[command(
+        about = "Print offset, hex and ascii columns like xxd, or rebuild binary from a dump"
+    )]
+    Dump(HexDumpOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct HexDumpOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 每行的字节数
+    #[arg(short, long, default_value_t = 16)]
+    pub cols: usize,
+
+    // 每组的字节数，0 表示不分组
+    #[arg(short, long, default_value_t = 2)]
+    pub group: usize,
+
+    // 跳过开头的字节数
+    #[arg(short, long, default_value_t = 0)]
+    pub seek: u64,
+
+    // 最多输出的字节数
+    #[arg(short, long)]
+    pub length: Option<u64>,
+
+    // 把 hex dump 还原为二进制数据
+    #[arg(short, long, default_value_t = false)]
+    pub reverse: bool,
+}
+
+impl CmdExecutor for HexDumpOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+
+        if self.reverse {
+            return process_hex_reverse(&mut reader, &mut writer);
+        }
+        process_hex_dump(
+            &mut reader,
+            &mut writer,
+            self.seek,
+            self.length,
+            self.cols,
+            self.group,
+        )
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{process_id_generate, process_id_inspect, CmdExecutor};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+const NANOID_ALPHABET: &str = "_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum IdSubCommand {
+    #[command(about = "Generate uuid v4/v7, ulid or nanoid identifiers")]
+    Generate(IdGenerateOpts),
+
+    #[command(about = "Show the version and embedded timestamp of a uuid or ulid")]
+    Inspect(IdInspectOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct IdGenerateOpts {
+    #[arg(short, long, default_value = "uuid4", value_parser = parse_id_kind)]
+    pub kind: IdKind,
+
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    // nanoid 使用的字母表和长度
+    #[arg(long, default_value = NANOID_ALPHABET)]
+    pub alphabet: String,
+
+    #[arg(short, long, default_value_t = 21)]
+    pub length: usize,
+
+    // 按嵌入的时间排序，只支持 uuid7 和 ulid
+    #[arg(long, default_value_t = false)]
+    pub sort: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct IdInspectOpts {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum IdKind {
+    Uuid4,
+    Uuid7,
+    Ulid,
+    Nanoid,
+}
+
+fn parse_id_kind(kind: &str) -> Result<IdKind, anyhow::Error> {
+    kind.parse()
+}
+
+impl FromStr for IdKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uuid4" => Ok(IdKind::Uuid4),
+            "uuid7" => Ok(IdKind::Uuid7),
+            "ulid" => Ok(IdKind::Ulid),
+            "nanoid" => Ok(IdKind::Nanoid),
+            _ => Err(anyhow::anyhow!("Invalid id kind: {}", s)),
+        }
+    }
+}
+
+impl From<IdKind> for &'static str {
+    fn from(kind: IdKind) -> Self {
+        match kind {
+            IdKind::Uuid4 => "uuid4",
+            IdKind::Uuid7 => "uuid7",
+            IdKind::Ulid => "ulid",
+            IdKind::Nanoid => "nanoid",
+        }
+    }
+}
+
+impl fmt::Display for IdKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for IdGenerateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let ids = process_id_generate(
+            self.kind,
+            self.count,
+            &self.alphabet,
+            self.length,
+            self.sort,
+        )?;
+        for id in ids {
+            println!("{}", id);
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for IdInspectOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let info = process_id_inspect(&self.id)?;
+        println!("kind: {}", info.kind);
+        if let Some(version) = info.version {
+            println!("version: {}", version);
+        }
+        match info.timestamp {
+            Some(ts) => println!("timestamp: {}", humantime::format_rfc3339_millis(ts)),
+            None => println!("timestamp: none"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use super::verify_file;
+use crate::{
+    check_time_claims, format_claim_time, get_content, get_key, jwt_expiration, process_jwt_decode,
+    process_jwt_sign, process_jwt_verify, unix_time, ClaimCheck, CmdExecutor,
+};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use serde_json::{Map, Value};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, UNIX_EPOCH},
+};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum JwtSubCommand {
+    #[command(about = "Sign a JWT with a HS256 shared secret or an ed25519 signing key")]
+    Sign(JwtSignOpts),
+
+    #[command(about = "Verify the signature and the exp/nbf/iat claims of a JWT")]
+    Verify(JwtVerifyOpts),
+
+    #[command(about = "Pretty-print the header and claims of a JWT without verifying it")]
+    Decode(JwtDecodeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct JwtSignOpts {
+    // HS256 的共享密钥文件，或者 text generate 生成的 ed25519.signing_key
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+
+    #[arg(long, default_value = "HS256", value_parser = parse_jwt_algorithm)]
+    pub alg: JwtAlgorithm,
+
+    // 包含自定义 claims 的 json 文件
+    #[arg(long, value_parser = verify_file)]
+    pub claims: Option<String>,
+
+    #[arg(long)]
+    pub sub: Option<String>,
+
+    #[arg(long)]
+    pub aud: Option<String>,
+
+    // 有效期，例如 15m、1h、7d
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub exp: Option<Duration>,
+}
+
+#[derive(Debug, Parser)]
+pub struct JwtVerifyOpts {
+    pub token: String,
+
+    // HS256 的共享密钥文件，或者 ed25519.verifying_key
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+
+    #[arg(long, default_value = "HS256", value_parser = parse_jwt_algorithm)]
+    pub alg: JwtAlgorithm,
+
+    // 检查 exp/nbf/iat 时使用的时间，unix 秒数或者 RFC 3339，默认当前时间
+    #[arg(long, value_parser = parse_now)]
+    pub now: Option<u64>,
+
+    // 允许的时钟偏差，单位秒
+    #[arg(long, default_value_t = 0)]
+    pub leeway: u64,
+}
+
+#[derive(Debug, Parser)]
+pub struct JwtDecodeOpts {
+    pub token: String,
+
+    #[arg(long, value_parser = parse_now)]
+    pub now: Option<u64>,
+
+    #[arg(long, default_value_t = 0)]
+    pub leeway: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum JwtAlgorithm {
+    Hs256,
+    EdDsa,
+}
+
+fn parse_jwt_algorithm(alg: &str) -> Result<JwtAlgorithm, anyhow::Error> {
+    alg.parse()
+}
+
+fn parse_now(now: &str) -> Result<u64, anyhow::Error> {
+    if let Ok(secs) = now.parse() {
+        return Ok(secs);
+    }
+    let time = humantime::parse_rfc3339_weak(now)?;
+    Ok(time.duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+impl FromStr for JwtAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HS256" | "hs256" => Ok(JwtAlgorithm::Hs256),
+            "EdDSA" | "eddsa" => Ok(JwtAlgorithm::EdDsa),
+            _ => Err(anyhow::anyhow!("Invalid jwt algorithm: {}", s)),
+        }
+    }
+}
+
+impl From<JwtAlgorithm> for &'static str {
+    fn from(alg: JwtAlgorithm) -> Self {
+        match alg {
+            JwtAlgorithm::Hs256 => "HS256",
+            JwtAlgorithm::EdDsa => "EdDSA",
+        }
+    }
+}
+
+impl fmt::Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn print_checks(checks: &[ClaimCheck]) {
+    for check in checks {
+        let time = format_claim_time(check.time)
+            .unwrap_or_else(|| format!("{} [out of range]", check.time));
+        let status = match (check.name, check.valid) {
+            (_, true) => "ok",
+            ("exp", false) => "expired",
+            ("nbf", false) => "not yet valid",
+            _ => "issued in the future",
+        };
+        let mark = if check.valid { "✓" } else { "⚠" };
+        println!("{} {}: {} ({})", mark, check.name, time, status);
+    }
+}
+
+impl CmdExecutor for JwtSignOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = get_key(&self.key)?;
+
+        let mut claims = match &self.claims {
+            Some(path) => match serde_json::from_slice(&get_content(path)?)? {
+                Value::Object(claims) => claims,
+                _ => anyhow::bail!("JWT claims must be a json object"),
+            },
+            None => Map::new(),
+        };
+        if let Some(sub) = self.sub {
+            claims.insert("sub".to_string(), sub.into());
+        }
+        if let Some(aud) = self.aud {
+            claims.insert("aud".to_string(), aud.into());
+        }
+        let now = unix_time()?;
+        claims.insert("iat".to_string(), now.into());
+        if let Some(exp) = self.exp {
+            claims.insert("exp".to_string(), jwt_expiration(now, exp)?.into());
+        }
+
+        let token = process_jwt_sign(&claims, &key, self.alg)?;
+        println!("{}", token);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for JwtVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = get_key(&self.key)?;
+        let now = match self.now {
+            Some(now) => now,
+            None => unix_time()?,
+        };
+
+        let ret = process_jwt_verify(&self.token, &key, self.alg, now, self.leeway)?;
+        if ret.signature_valid {
+            println!("✓ Signature verified");
+        } else {
+            println!("⚠ Signature not verified");
+        }
+        print_checks(&ret.checks);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for JwtDecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let jwt = process_jwt_decode(&self.token)?;
+        let now = match self.now {
+            Some(now) => now,
+            None => unix_time()?,
+        };
+
+        println!("{}", serde_json::to_string_pretty(&jwt.header)?);
+        println!("{}", serde_json::to_string_pretty(&jwt.claims)?);
+        print_checks(&check_time_claims(&jwt.claims, now, self.leeway)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use super::verify_file;
+use crate::{
+    decode_signing_key, encode_signing_key, encode_verifying_key, get_content, get_writer,
+    is_protected_key, keystore_dir, process_key_import, process_key_list, process_key_private,
+    process_key_remove, process_key_resolve, process_key_rotate, process_key_signing_key,
+    process_protect_key, read_passphrase, read_passphrase_from, unlock_key, write_private_file,
+    CmdExecutor, KeyFormat,
+};
+use clap::Parser;
+use ed25519_dalek::VerifyingKey;
+use enum_dispatch::enum_dispatch;
+use std::{io::Write, path::Path};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum KeySubCommand {
+    #[command(about = "List keys in the keystore with their key ids")]
+    List(KeyListOpts),
+
+    #[command(about = "Import an ed25519 private or public key into the keystore")]
+    Import(KeyImportOpts),
+
+    #[command(about = "Export a public key, or the current private key")]
+    Export(KeyExportOpts),
+
+    #[command(
+        name = "rm",
+        about = "Remove a key and all its versions from the keystore"
+    )]
+    Remove(KeyRemoveOpts),
+
+    #[command(about = "Replace a key with a new one, keeping old public keys for verification")]
+    Rotate(KeyRotateOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyListOpts {
+    // 同时列出已经轮换的旧版本
+    #[arg(short, long, default_value_t = false)]
+    pub all: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyImportOpts {
+    pub name: String,
+
+    // raw、PKCS#8 PEM 或 OpenSSH 格式，被 passphrase 保护的私钥会保持加密
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    // 输入是 raw 格式的公钥，PEM 和 OpenSSH 格式的公钥会自动识别
+    #[arg(long, default_value_t = false)]
+    pub public: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyExportOpts {
+    // 名称或者 key id，key id 可以导出已经轮换的旧公钥
+    pub key_id: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    #[arg(long, default_value = "openssh", value_parser = parse_key_format)]
+    pub key_format: KeyFormat,
+
+    // 导出当前版本的私钥，输出是明文
+    #[arg(long, default_value_t = false)]
+    pub private: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyRemoveOpts {
+    pub name: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyRotateOpts {
+    pub name: String,
+
+    // 使用 passphrase 保护新的私钥，原来的私钥被保护时总是会保护新的私钥
+    #[arg(long, default_value_t = false)]
+    pub passphrase: bool,
+}
+
+fn parse_key_format(format: &str) -> Result<KeyFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl CmdExecutor for KeyListOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let entries = process_key_list(&keystore_dir()?)?;
+        if entries.is_empty() {
+            eprintln!("No keys in the keystore");
+            return Ok(());
+        }
+
+        println!(
+            "{:<20} {:<16} {:<8} {:<20} STATUS",
+            "NAME", "KEY ID", "TYPE", "CREATED"
+        );
+        for entry in entries {
+            let kind = if entry.has_private {
+                "private"
+            } else {
+                "public"
+            };
+            for version in entry.versions.iter().rev() {
+                let status = match &version.retired {
+                    Some(retired) => format!("retired {}", retired),
+                    None => "current".to_string(),
+                };
+                println!(
+                    "{:<20} {:<16} {:<8} {:<20} {}",
+                    entry.name, version.key_id, kind, version.created, status
+                );
+                if !self.all {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyImportOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let data = get_content(&self.input)?;
+        let entry = process_key_import(&keystore_dir()?, &self.name, &data, self.public, || {
+            read_passphrase(false)
+        })?;
+        println!("Imported {} ({})", entry.name, entry.current().key_id);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyExportOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let store = keystore_dir()?;
+        let content = if self.private {
+            let (name, key) = process_key_signing_key(&store, &self.key_id)?;
+            let key = decode_signing_key(&unlock_key(&key, &name)?)?;
+            encode_signing_key(&key, self.key_format)?
+        } else {
+            let (_, version) = process_key_resolve(&store, &self.key_id)?;
+            let key: [u8; 32] = version
+                .public_key()?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid public key in the keystore"))?;
+            encode_verifying_key(&VerifyingKey::from_bytes(&key)?, self.key_format)?
+        };
+
+        // 导出的私钥没有 passphrase 保护，只允许当前用户读写
+        if self.private && self.output != "-" {
+            return write_private_file(Path::new(&self.output), &content);
+        }
+        let mut writer = get_writer(&self.output)?;
+        writer.write_all(&content)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyRemoveOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_key_remove(&keystore_dir()?, &self.name)?;
+        println!("Removed {}", self.name);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyRotateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let store = keystore_dir()?;
+        let protect =
+            self.passphrase || is_protected_key(&process_key_private(&store, &self.name)?);
+        let entry = process_key_rotate(&store, &self.name, |key| {
+            if !protect {
+                return Ok(key);
+            }
+            let passphrase = read_passphrase_from("RCLI_NEW_PASSPHRASE", "New passphrase: ", true)?;
+            process_protect_key(&key, &passphrase)
+        })?;
+
+        let retired = &entry.versions[entry.versions.len() - 2];
+        println!(
+            "Rotated {}: {} -> {}",
+            entry.name,
+            retired.key_id,
+            entry.current().key_id
+        );
+        Ok(())
+    }
+}
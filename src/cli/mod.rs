@@ -1,14 +1,25 @@
 mod base64;
 mod csv;
 mod genpass;
+mod hash;
+mod hex;
 mod http;
+mod id;
+mod jwt;
+mod key;
+mod otp;
 mod text;
+mod token;
+mod url;
 
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
 use std::path::{Path, PathBuf};
 
-pub use self::{base64::*, csv::*, genpass::*, http::*, text::*};
+pub use self::{
+    base64::*, csv::*, genpass::*, hash::*, hex::*, http::*, id::*, jwt::*, key::*, otp::*,
+    text::*, token::*, url::*,
+};
 
 /// 最上层的 command
 /// Parser 是 clap 的属性，它是用来解析命令行参数的
@@ -35,14 +46,69 @@ pub enum SubCommand {
 
     // base64 下面还有子命令，因此需要加 subcommand 属性，而不是像 csv 那样直接定义在 Opts 中
     // 如果这里不加 name 属性，会自动将 enum 的名称小写作为 command 的名称
-    #[command(subcommand, name = "base64", about = "Encode and decode base64")]
+    #[command(
+        subcommand,
+        name = "base64",
+        visible_alias = "codec",
+        about = "Encode and decode base64, base32, base58, hex or ascii85"
+    )]
     Base64(Base64SubCommand),
 
+    #[command(
+        name = "hash",
+        about = "Compute or check blake3, SHA-256, SHA-512 or SHA-1 checksums"
+    )]
+    Hash(HashOpts),
+
     #[command(subcommand, name = "text", about = "Sign or verify a text")]
     Text(TextSubCommand),
 
     #[command(subcommand, name = "http", about = "Start a http file server")]
     Http(HttpSubCommand),
+
+    #[command(subcommand, name = "otp", about = "Generate or verify HOTP/TOTP codes")]
+    Otp(OtpSubCommand),
+
+    #[command(
+        subcommand,
+        name = "token",
+        about = "Generate or verify prefixed API tokens"
+    )]
+    Token(TokenSubCommand),
+
+    #[command(
+        subcommand,
+        name = "id",
+        about = "Generate or inspect uuid, ulid and nanoid"
+    )]
+    Id(IdSubCommand),
+
+    #[command(
+        subcommand,
+        name = "url",
+        about = "Percent-encode or decode url components"
+    )]
+    Url(UrlSubCommand),
+
+    #[command(subcommand, name = "qp", about = "Encode or decode quoted-printable")]
+    Qp(QpSubCommand),
+
+    #[command(
+        subcommand,
+        name = "jwt",
+        about = "Decode, sign or verify JSON Web Tokens"
+    )]
+    Jwt(JwtSubCommand),
+
+    #[command(subcommand, name = "hex", about = "View binary data as a hex dump")]
+    Hex(HexSubCommand),
+
+    #[command(
+        subcommand,
+        name = "key",
+        about = "Manage ed25519 keys in the local keystore"
+    )]
+    Key(KeySubCommand),
 }
 
 // 这些代码是非常机械的，所以可以使用 enum dispatch
@@ -0,0 +1,257 @@
+use crate::{process_otp_secret, process_otpauth_uri, unix_time, CmdExecutor, Otp};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a new base32 secret and its otpauth:// URI")]
+    Secret(OtpSecretOpts),
+
+    #[command(about = "Generate a HOTP or TOTP code from a base32 secret")]
+    Generate(OtpGenerateOpts),
+
+    #[command(about = "Verify a HOTP or TOTP code")]
+    Verify(OtpVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpSecretOpts {
+    #[arg(long, default_value = "totp", value_parser = parse_otp_kind)]
+    pub kind: OtpKind,
+
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+
+    #[arg(long, default_value_t = 6, value_parser = parse_otp_digits)]
+    pub digits: u32,
+
+    // TOTP 的时间步长，单位秒
+    #[arg(long, default_value_t = 30, value_parser = parse_otp_period)]
+    pub period: u64,
+
+    // HOTP 的初始 counter
+    #[arg(long, default_value_t = 0)]
+    pub counter: u64,
+
+    // secret 的字节数，RFC 4226 推荐 160 bit
+    #[arg(short, long, default_value_t = 20)]
+    pub length: usize,
+
+    #[arg(long, default_value = "rcli")]
+    pub issuer: String,
+
+    #[arg(long, default_value = "rcli")]
+    pub account: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    // base32 编码的 secret
+    #[arg(short, long)]
+    pub secret: String,
+
+    #[arg(long, default_value = "totp", value_parser = parse_otp_kind)]
+    pub kind: OtpKind,
+
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+
+    #[arg(long, default_value_t = 6, value_parser = parse_otp_digits)]
+    pub digits: u32,
+
+    #[arg(long, default_value_t = 30, value_parser = parse_otp_period)]
+    pub period: u64,
+
+    #[arg(long, default_value_t = 0)]
+    pub counter: u64,
+
+    // unix 时间戳，不指定则使用当前时间
+    #[arg(long)]
+    pub time: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    #[arg(short, long)]
+    pub secret: String,
+
+    #[arg(short, long)]
+    pub code: String,
+
+    #[arg(long, default_value = "totp", value_parser = parse_otp_kind)]
+    pub kind: OtpKind,
+
+    #[arg(long, default_value = "sha1", value_parser = parse_otp_algorithm)]
+    pub algorithm: OtpAlgorithm,
+
+    #[arg(long, default_value_t = 6, value_parser = parse_otp_digits)]
+    pub digits: u32,
+
+    #[arg(long, default_value_t = 30, value_parser = parse_otp_period)]
+    pub period: u64,
+
+    #[arg(long, default_value_t = 0)]
+    pub counter: u64,
+
+    #[arg(long)]
+    pub time: Option<u64>,
+
+    // TOTP 允许前后偏移的时间步长数，HOTP 则是 counter 向后查找的窗口
+    #[arg(long, default_value_t = 1)]
+    pub skew: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpKind {
+    Totp,
+    Hotp,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_otp_kind(kind: &str) -> Result<OtpKind, anyhow::Error> {
+    kind.parse()
+}
+
+fn parse_otp_algorithm(algorithm: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+// authenticator app 只支持 6 到 8 位
+fn parse_otp_digits(digits: &str) -> Result<u32, anyhow::Error> {
+    let digits = digits.parse()?;
+    if !(6..=8).contains(&digits) {
+        anyhow::bail!("OTP digits must be between 6 and 8, got {}", digits);
+    }
+    Ok(digits)
+}
+
+fn parse_otp_period(period: &str) -> Result<u64, anyhow::Error> {
+    let period = period.parse()?;
+    if period == 0 {
+        anyhow::bail!("OTP period must be greater than 0");
+    }
+    Ok(period)
+}
+
+impl FromStr for OtpKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "totp" => Ok(OtpKind::Totp),
+            "hotp" => Ok(OtpKind::Hotp),
+            _ => Err(anyhow::anyhow!("Invalid otp kind: {}", s)),
+        }
+    }
+}
+
+impl From<OtpKind> for &'static str {
+    fn from(kind: OtpKind) -> Self {
+        match kind {
+            OtpKind::Totp => "totp",
+            OtpKind::Hotp => "hotp",
+        }
+    }
+}
+
+impl fmt::Display for OtpKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Invalid otp algorithm: {}", s)),
+        }
+    }
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for OtpSecretOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let secret = process_otp_secret(self.length);
+        let moving_factor = match self.kind {
+            OtpKind::Totp => self.period,
+            OtpKind::Hotp => self.counter,
+        };
+        let uri = process_otpauth_uri(
+            self.kind,
+            &secret,
+            &self.issuer,
+            &self.account,
+            self.algorithm,
+            self.digits,
+            moving_factor,
+        );
+
+        println!("{}", secret);
+        println!("{}", uri);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpGenerateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let otp = Otp::try_new(&self.secret, self.algorithm, self.digits)?;
+        let code = match self.kind {
+            OtpKind::Totp => otp.totp(self.time.map_or_else(unix_time, Ok)?, self.period)?,
+            OtpKind::Hotp => otp.hotp(self.counter)?,
+        };
+
+        println!("{}", code);
+        Ok(())
+    }
+}
+
+impl CmdExecutor for OtpVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let otp = Otp::try_new(&self.secret, self.algorithm, self.digits)?;
+        let code = self.code.trim();
+
+        match self.kind {
+            OtpKind::Totp => {
+                let time = self.time.map_or_else(unix_time, Ok)?;
+                match otp.verify_totp(code, time, self.period, self.skew)? {
+                    Some(offset) => println!("✓ Code verified (time step offset {})", offset),
+                    None => println!("⚠ Code not verified"),
+                }
+            }
+            OtpKind::Hotp => match otp.verify_hotp(code, self.counter, self.skew)? {
+                Some(counter) => println!("✓ Code verified (counter {})", counter),
+                None => println!("⚠ Code not verified"),
+            },
+        }
+
+        Ok(())
+    }
+}
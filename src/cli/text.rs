@@ -1,13 +1,23 @@
 use super::{verify_file, verify_path};
 use crate::{
-    get_content, get_reader, process_text_key_generate, process_text_sign, process_text_verify,
-    CmdExecutor,
+    encode_signature, get_key, get_reader, get_writer, keystore_dir, minisign_signature_comment,
+    minisign_trusted_comment, parse_identities, parse_recipient, process_age_encrypt,
+    process_allowed_signers_key, process_key_convert, process_key_resolve, process_key_signing_key,
+    process_manifest_build, process_minisign_sign, process_parse_signature, process_protect_key,
+    process_signer_key_id, process_sshsig_sign, process_sshsig_verify, process_text_decrypt,
+    process_text_encrypt, process_text_key_generate, process_text_sign, process_text_sign_mmap,
+    process_text_verify, process_text_verify_mmap, process_unprotect_key, process_verifier_key_id,
+    read_passphrase, read_passphrase_from, unlock_key, write_private_file, CmdExecutor,
+    EncryptionKey, Manifest, ManifestDiff, ParsedSignature, SignatureEnvelope, PUBLIC_KEY_FILES,
 };
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
-use base64::Engine;
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
-use std::{fmt, path::PathBuf, str::FromStr};
+use std::{
+    fmt,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -20,9 +30,28 @@ pub enum TextSubCommand {
 
     #[command(
         name = "generate",
-        about = "Generate a random blake3 key or ed25519 key pair"
+        about = "Generate a random blake3 or HMAC key, or an ed25519 key pair"
     )]
     KeyGenerate(KeyGenerateOpts),
+
+    #[command(subcommand, about = "Manage ed25519 key files")]
+    Key(TextKeySubCommand),
+
+    #[command(about = "Encrypt a file with a blake3 key file, a passphrase or to age recipients")]
+    Encrypt(TextEncryptOpts),
+
+    #[command(about = "Decrypt a file produced by text encrypt")]
+    Decrypt(TextDecryptOpts),
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum TextKeySubCommand {
+    #[command(about = "Convert an ed25519 key between raw, PKCS#8 PEM and OpenSSH formats")]
+    Convert(KeyConvertOpts),
+
+    #[command(about = "Protect a private key file with a passphrase, or change/remove it")]
+    Passwd(KeyPasswdOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -30,11 +59,47 @@ pub struct TextSignOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, value_parser = verify_file, required_unless_present = "key_id")]
+    pub key: Option<String>,
 
-    #[arg(long, default_value = "blake3", value_parser = parse_text_sign_format)]
-    pub format: TextSignFormat,
+    // keystore 中的密钥名称或者 key id，只能使用当前版本签名
+    #[arg(long, conflicts_with = "key")]
+    pub key_id: Option<String>,
+
+    // 默认为 blake3，使用 keystore 时默认为 ed25519
+    #[arg(long, value_parser = parse_text_sign_format)]
+    pub format: Option<TextSignFormat>,
+
+    // 通过 mmap 和多线程计算 blake3，只支持文件输入
+    #[arg(long, default_value_t = false)]
+    pub mmap: bool,
+
+    // 签名整个目录：生成每个文件的路径、大小和 blake3 hash 的 manifest，然后签名 manifest
+    #[arg(long, value_parser = verify_path, conflicts_with_all = ["input", "mmap"], requires = "manifest")]
+    pub dir: Option<PathBuf>,
+
+    // --dir 生成的 manifest 写入的文件
+    #[arg(long, requires = "dir")]
+    pub manifest: Option<String>,
+
+    // 输出包含算法、key id、时间的签名文件，而不是裸的 base64 签名
+    #[arg(long, value_parser = parse_envelope_format)]
+    pub envelope: Option<EnvelopeFormat>,
+
+    // 裸签名的编码：base64url（不带 padding）、标准 base64 或者 hex
+    #[arg(long, default_value = "base64url", value_parser = parse_signature_encoding, conflicts_with = "envelope")]
+    pub encoding: SignatureEncoding,
+
+    // 写入签名文件的备注，envelope 的备注不在签名范围内，minisign 的 trusted comment 在签名范围内
+    #[arg(long)]
+    pub comment: Option<String>,
+
+    // sshsig 签名的用途，和 ssh-keygen -Y sign -n 相同，例如 file 或 git
+    #[arg(long)]
+    pub namespace: Option<String>,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
 }
 
 #[derive(Debug, Parser)]
@@ -44,14 +109,45 @@ pub struct TextVerifyOPts {
     pub input: String,
 
     // verify key
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, value_parser = verify_file, required_unless_present_any = ["key_id", "allowed_signers"])]
+    pub key: Option<String>,
+
+    // keystore 中的密钥名称或者 key id，签名文件中的 key id 可以选中轮换前的旧公钥
+    #[arg(long, conflicts_with = "key")]
+    pub key_id: Option<String>,
+
+    // OpenSSH 的 allowed_signers 文件，从中查找 principal 对应的公钥，只用于 sshsig
+    #[arg(long, value_parser = verify_file, conflicts_with_all = ["key", "key_id"], requires = "principal")]
+    pub allowed_signers: Option<String>,
+
+    #[arg(long, requires = "allowed_signers")]
+    pub principal: Option<String>,
 
+    // sshsig 签名的用途，必须和签名时的一致
+    #[arg(long)]
+    pub namespace: Option<String>,
+
+    // 签名文件的路径，或者 base64 编码的签名
     #[arg(long)]
     pub sig: String,
 
-    #[arg(long, default_value = "blake3", value_parser = parse_text_sign_format)]
-    pub format: TextSignFormat,
+    // 裸签名的编码，签名文件不受影响
+    #[arg(long, default_value = "base64url", value_parser = parse_signature_encoding)]
+    pub encoding: SignatureEncoding,
+
+    // 不指定时使用签名文件中的算法，裸签名默认为 blake3，使用 keystore 时默认为 ed25519
+    #[arg(long, value_parser = parse_text_sign_format)]
+    pub format: Option<TextSignFormat>,
+
+    #[arg(long, default_value_t = false)]
+    pub mmap: bool,
+
+    // 校验目录和签名的 manifest 是否一致，报告新增、缺失和修改的文件
+    #[arg(long, value_parser = verify_path, conflicts_with_all = ["input", "mmap"], requires = "manifest")]
+    pub dir: Option<PathBuf>,
+
+    #[arg(long, value_parser = verify_file, requires = "dir")]
+    pub manifest: Option<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -61,18 +157,161 @@ pub struct KeyGenerateOpts {
 
     #[arg(short, long, value_parser = verify_path)]
     pub output_path: PathBuf,
+
+    // ed25519 密钥的文件格式，blake3 只支持 raw
+    #[arg(long, default_value = "raw", value_parser = parse_key_format)]
+    pub key_format: KeyFormat,
+
+    // 使用 passphrase 加密私钥文件，从 RCLI_PASSPHRASE(_FD) 读取或者在终端输入
+    #[arg(long, default_value_t = false)]
+    pub passphrase: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Parser)]
+pub struct TextEncryptOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // text generate 生成的 blake3 密钥文件
+    #[arg(
+        short,
+        long,
+        value_parser = verify_file,
+        required_unless_present_any = ["passphrase", "recipient"]
+    )]
+    pub key: Option<String>,
+
+    // 使用 passphrase 派生密钥，从 RCLI_PASSPHRASE 读取或者在终端输入
+    #[arg(long, default_value_t = false, conflicts_with = "key")]
+    pub passphrase: bool,
+
+    #[arg(long, default_value = "xchacha20poly1305", value_parser = parse_text_cipher)]
+    pub cipher: TextCipher,
+
+    // age1 公钥或者公钥文件（age 或 ed25519），可以指定多次，输出 age 格式
+    #[arg(short, long, conflicts_with_all = ["key", "passphrase"])]
+    pub recipient: Vec<String>,
+
+    // 输出 ascii armor 格式的 age 文件
+    #[arg(long, default_value_t = false, requires = "recipient")]
+    pub armor: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct TextDecryptOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 算法和是否使用 passphrase 都记录在文件 header 中，使用 passphrase 加密的文件不需要 key
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: Option<String>,
+
+    // 解密 age 文件使用的 identity 文件（age 或 ed25519 私钥），可以指定多次
+    #[arg(long, value_parser = verify_file)]
+    pub identity: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyConvertOpts {
+    // 输入的格式会自动识别
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    #[arg(long, value_parser = parse_key_format)]
+    pub to: KeyFormat,
+
+    // 输出公钥，输入是私钥时导出对应的公钥，输入是 raw 格式时当作公钥
+    #[arg(long, default_value_t = false)]
+    pub public: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyPasswdOpts {
+    // 原来的 passphrase 从 RCLI_PASSPHRASE(_FD) 读取，新的从 RCLI_NEW_PASSPHRASE(_FD) 读取
+    #[arg(short, long, value_parser = verify_file)]
+    pub input: String,
+
+    // 默认覆盖输入文件
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    // 去掉 passphrase，以明文保存私钥
+    #[arg(long, default_value_t = false)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
+    // 流式读取输入，先做 SHA-512 再签名，使用和 ed25519 相同的密钥
+    Ed25519ph,
+    // 和 minisign 兼容的 .minisig 签名文件，使用 ed25519 密钥
+    Minisign,
+    // 和 ssh-keygen -Y sign 兼容的 SSH 签名，使用 ed25519 密钥
+    Sshsig,
+    // 任意长度的对称密钥，和 webhook 的签名兼容
+    HmacSha256,
+    HmacSha512,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TextCipher {
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum KeyFormat {
+    Raw,
+    Pem,
+    Openssh,
+    // 只支持公钥
+    Minisign,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EnvelopeFormat {
+    Json,
+    Yaml,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SignatureEncoding {
+    Base64Url,
+    Base64,
+    Hex,
 }
 
 fn parse_text_sign_format(format: &str) -> Result<TextSignFormat, anyhow::Error> {
     format.parse()
 }
 
+fn parse_envelope_format(format: &str) -> Result<EnvelopeFormat, anyhow::Error> {
+    format.parse()
+}
+
+fn parse_signature_encoding(encoding: &str) -> Result<SignatureEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+fn parse_key_format(format: &str) -> Result<KeyFormat, anyhow::Error> {
+    format.parse()
+}
+
+fn parse_text_cipher(cipher: &str) -> Result<TextCipher, anyhow::Error> {
+    cipher.parse()
+}
+
 impl FromStr for TextSignFormat {
     type Err = anyhow::Error;
 
@@ -80,6 +319,11 @@ impl FromStr for TextSignFormat {
         match s {
             "blake3" => Ok(TextSignFormat::Blake3),
             "ed25519" => Ok(TextSignFormat::Ed25519),
+            "ed25519ph" => Ok(TextSignFormat::Ed25519ph),
+            "minisign" => Ok(TextSignFormat::Minisign),
+            "sshsig" => Ok(TextSignFormat::Sshsig),
+            "hmac-sha256" => Ok(TextSignFormat::HmacSha256),
+            "hmac-sha512" => Ok(TextSignFormat::HmacSha512),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -90,6 +334,11 @@ impl From<TextSignFormat> for &'static str {
         match format {
             TextSignFormat::Blake3 => "blake3",
             TextSignFormat::Ed25519 => "ed25519",
+            TextSignFormat::Ed25519ph => "ed25519ph",
+            TextSignFormat::Minisign => "minisign",
+            TextSignFormat::Sshsig => "sshsig",
+            TextSignFormat::HmacSha256 => "hmac-sha256",
+            TextSignFormat::HmacSha512 => "hmac-sha512",
         }
     }
 }
@@ -100,44 +349,471 @@ impl fmt::Display for TextSignFormat {
     }
 }
 
+impl FromStr for TextCipher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chacha20poly1305" => Ok(TextCipher::ChaCha20Poly1305),
+            "xchacha20poly1305" => Ok(TextCipher::XChaCha20Poly1305),
+            _ => Err(anyhow::anyhow!("Invalid cipher: {}", s)),
+        }
+    }
+}
+
+impl From<TextCipher> for &'static str {
+    fn from(cipher: TextCipher) -> Self {
+        match cipher {
+            TextCipher::ChaCha20Poly1305 => "chacha20poly1305",
+            TextCipher::XChaCha20Poly1305 => "xchacha20poly1305",
+        }
+    }
+}
+
+impl fmt::Display for TextCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(KeyFormat::Raw),
+            "pem" => Ok(KeyFormat::Pem),
+            "openssh" => Ok(KeyFormat::Openssh),
+            "minisign" => Ok(KeyFormat::Minisign),
+            _ => Err(anyhow::anyhow!("Invalid key format: {}", s)),
+        }
+    }
+}
+
+impl From<KeyFormat> for &'static str {
+    fn from(format: KeyFormat) -> Self {
+        match format {
+            KeyFormat::Raw => "raw",
+            KeyFormat::Pem => "pem",
+            KeyFormat::Openssh => "openssh",
+            KeyFormat::Minisign => "minisign",
+        }
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for EnvelopeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(EnvelopeFormat::Json),
+            "yaml" => Ok(EnvelopeFormat::Yaml),
+            _ => Err(anyhow::anyhow!("Invalid envelope format: {}", s)),
+        }
+    }
+}
+
+impl From<EnvelopeFormat> for &'static str {
+    fn from(format: EnvelopeFormat) -> Self {
+        match format {
+            EnvelopeFormat::Json => "json",
+            EnvelopeFormat::Yaml => "yaml",
+        }
+    }
+}
+
+impl fmt::Display for EnvelopeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for SignatureEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64url" => Ok(SignatureEncoding::Base64Url),
+            "base64" => Ok(SignatureEncoding::Base64),
+            "hex" => Ok(SignatureEncoding::Hex),
+            _ => Err(anyhow::anyhow!("Invalid signature encoding: {}", s)),
+        }
+    }
+}
+
+impl From<SignatureEncoding> for &'static str {
+    fn from(encoding: SignatureEncoding) -> Self {
+        match encoding {
+            SignatureEncoding::Base64Url => "base64url",
+            SignatureEncoding::Base64 => "base64",
+            SignatureEncoding::Hex => "hex",
+        }
+    }
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn check_mmap(input: &str, format: TextSignFormat) -> anyhow::Result<()> {
+    if !matches!(format, TextSignFormat::Blake3) {
+        anyhow::bail!("--mmap only supports the blake3 format");
+    }
+    if input == "-" {
+        anyhow::bail!("--mmap needs a file input, not stdin");
+    }
+    Ok(())
+}
+
+fn print_manifest_diff(diff: &ManifestDiff) {
+    if diff.is_empty() {
+        println!("✓ Directory matches the manifest");
+        return;
+    }
+
+    println!(
+        "⚠ Directory does not match the manifest: {} added, {} missing, {} modified",
+        diff.added.len(),
+        diff.missing.len(),
+        diff.modified.len()
+    );
+    for path in &diff.added {
+        println!("  added: {}", path);
+    }
+    for path in &diff.missing {
+        println!("  missing: {}", path);
+    }
+    for path in &diff.modified {
+        println!("  modified: {}", path);
+    }
+}
+
+// 和 ssh-keygen 一样要求显式指定 namespace，避免不同用途的签名被混用
+fn sshsig_namespace(namespace: &Option<String>) -> anyhow::Result<&str> {
+    namespace
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("sshsig signatures need --namespace, e.g. file or git"))
+}
+
+// 签名中带有公钥，allowed_signers 决定这个公钥是否可以代表 principal
+fn allowed_signers_key(opts: &TextVerifyOPts, sig: &ParsedSignature) -> anyhow::Result<Vec<u8>> {
+    let (Some(path), Some(principal)) = (&opts.allowed_signers, &opts.principal) else {
+        anyhow::bail!("--allowed-signers requires --principal");
+    };
+    let ParsedSignature::Native(TextSignFormat::Sshsig, sig) = sig else {
+        anyhow::bail!("--allowed-signers only works with sshsig signatures");
+    };
+    let allowed_signers = std::fs::read_to_string(path)?;
+    let namespace = sshsig_namespace(&opts.namespace)?;
+    process_allowed_signers_key(&allowed_signers, principal, namespace, sig)
+}
+
+// keystore 中只有 ed25519 密钥
+fn default_format(keystore: bool) -> TextSignFormat {
+    if keystore {
+        TextSignFormat::Ed25519
+    } else {
+        TextSignFormat::Blake3
+    }
+}
+
+// 签名文件记录了 key id 时使用对应的版本，这样轮换之前的签名仍然可以验证
+fn keystore_public_key(id: &str, key_id: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    let (entry, version) = process_key_resolve(&keystore_dir()?, id)?;
+    let version = key_id.and_then(|k| entry.version(k)).unwrap_or(&version);
+    version.public_key()
+}
+
 impl CmdExecutor for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let mut reader = get_reader(&self.input)?;
-        let key = get_content(&self.key)?;
-        let sig = process_text_sign(&mut reader, &key, self.format)?;
+        let key = match (&self.key, &self.key_id) {
+            (_, Some(id)) => {
+                let (name, key) = process_key_signing_key(&keystore_dir()?, id)?;
+                unlock_key(&key, &name)?
+            }
+            (Some(key), None) => get_key(key)?,
+            (None, None) => anyhow::bail!("Either --key or --key-id is required"),
+        };
+        let format = self
+            .format
+            .unwrap_or_else(|| default_format(self.key_id.is_some()));
+        let minisign = format == TextSignFormat::Minisign;
+        // minisign 和 sshsig 有自己的签名文件格式
+        let native = minisign || format == TextSignFormat::Sshsig;
+        if native && self.envelope.is_some() {
+            anyhow::bail!(
+                "{} signatures have their own file format, drop --envelope",
+                format
+            );
+        }
+        if !minisign && self.comment.is_some() && self.envelope.is_none() {
+            anyhow::bail!("--comment needs --envelope or the minisign format");
+        }
+
+        // minisign 默认和 minisign 命令一样在 trusted comment 中记录时间和文件名
+        let signed_file = self
+            .manifest
+            .as_deref()
+            .or((self.input != "-").then_some(self.input.as_str()))
+            .and_then(|p| Path::new(p).file_name())
+            .and_then(|name| name.to_str());
+        let trusted_comment = match &self.comment {
+            Some(comment) => comment.clone(),
+            None => minisign_trusted_comment(signed_file),
+        };
+        let sign = |reader: &mut dyn Read| match format {
+            TextSignFormat::Minisign => process_minisign_sign(reader, &key, &trusted_comment),
+            TextSignFormat::Sshsig => {
+                process_sshsig_sign(reader, &key, sshsig_namespace(&self.namespace)?)
+            }
+            _ => process_text_sign(reader, &key, format),
+        };
+
+        let sig = if let (Some(dir), Some(path)) = (&self.dir, &self.manifest) {
+            let mut exclude = vec![Path::new(path)];
+            if self.output != "-" {
+                exclude.push(Path::new(&self.output));
+            }
+            let manifest = process_manifest_build(dir, &exclude)?.to_bytes();
+            std::fs::write(path, &manifest)?;
+            sign(&mut manifest.as_slice())?
+        } else if self.mmap {
+            check_mmap(&self.input, format)?;
+            process_text_sign_mmap(Path::new(&self.input), &key)?
+        } else {
+            let mut reader = get_reader(&self.input)?;
+            sign(&mut reader)?
+        };
 
-        let encoded = URL_SAFE_NO_PAD.encode(sig);
-        println!("{}", encoded);
+        let mut writer = get_writer(&self.output)?;
+        match self.envelope {
+            _ if native => writer.write_all(&sig)?,
+            Some(envelope_format) => {
+                let key_id = process_signer_key_id(&key, format)?;
+                let envelope = SignatureEnvelope::new(format, key_id, &sig, self.comment);
+                let content = envelope.serialize(envelope_format)?;
+                writeln!(writer, "{}", content.trim_end())?;
+            }
+            None => writeln!(writer, "{}", encode_signature(&sig, self.encoding))?,
+        }
+        writer.flush()?;
         Ok(())
     }
 }
 
 impl CmdExecutor for TextVerifyOPts {
     async fn execute(self) -> anyhow::Result<()> {
-        let mut reader = get_reader(&self.input)?;
-        let key = get_content(&self.key)?;
-        let decoded = URL_SAFE_NO_PAD.decode(&self.sig)?;
-        let verified = process_text_verify(&mut reader, &key, &decoded, self.format)?;
-        if verified {
+        let sig = process_parse_signature(&self.sig, self.encoding)?;
+        let key = match (&self.key, &self.key_id) {
+            _ if self.allowed_signers.is_some() => allowed_signers_key(&self, &sig)?,
+            (_, Some(id)) => {
+                let key_id = match &sig {
+                    ParsedSignature::Envelope(envelope) => Some(envelope.key_id.as_str()),
+                    _ => None,
+                };
+                keystore_public_key(id, key_id)?
+            }
+            (Some(key), None) => get_key(key)?,
+            (None, None) => anyhow::bail!("Either --key or --key-id is required"),
+        };
+        let (decoded, format, envelope) = match sig {
+            ParsedSignature::Envelope(envelope) => {
+                let format = envelope.format()?;
+                if let Some(expected) = self.format.filter(|f| *f != format) {
+                    anyhow::bail!("Signature uses {}, but --format is {}", format, expected);
+                }
+                let key_id = process_verifier_key_id(&key, format)?;
+                if key_id != envelope.key_id {
+                    anyhow::bail!(
+                        "Signature was made with key {}, but the given key is {}",
+                        envelope.key_id,
+                        key_id
+                    );
+                }
+                (envelope.signature()?, format, Some(envelope))
+            }
+            ParsedSignature::Raw(sig) => {
+                let format = self
+                    .format
+                    .unwrap_or_else(|| default_format(self.key_id.is_some()));
+                (sig, format, None)
+            }
+            ParsedSignature::Native(format, sig) => {
+                if let Some(expected) = self.format.filter(|f| *f != format) {
+                    anyhow::bail!("Signature uses {}, but --format is {}", format, expected);
+                }
+                (sig, format, None)
+            }
+        };
+
+        let verify = |reader: &mut dyn Read| match format {
+            TextSignFormat::Sshsig => {
+                let namespace = sshsig_namespace(&self.namespace)?;
+                process_sshsig_verify(reader, &key, &decoded, namespace)
+            }
+            _ => process_text_verify(reader, &key, &decoded, format),
+        };
+
+        let mut diff = None;
+        let verified = if let (Some(dir), Some(path)) = (&self.dir, &self.manifest) {
+            let content = std::fs::read(path)?;
+            let manifest = Manifest::parse(&content)?;
+            let exclude = [Path::new(path), Path::new(&self.sig)];
+            diff = Some(manifest.diff(&process_manifest_build(dir, &exclude)?));
+            verify(&mut content.as_slice())?
+        } else if self.mmap {
+            check_mmap(&self.input, format)?;
+            process_text_verify_mmap(Path::new(&self.input), &key, &decoded)?
+        } else {
+            let mut reader = get_reader(&self.input)?;
+            verify(&mut reader)?
+        };
+        // 签名只覆盖 manifest，目录和 manifest 不一致时不能算验证通过
+        let mismatched = diff.as_ref().is_some_and(|diff| !diff.is_empty());
+        if verified && !mismatched {
             println!("✓ Signature verified");
+        } else if verified {
+            println!("⚠ Signature verified, but the directory does not match the manifest");
         } else {
             println!("⚠ Signature not verified");
         }
+        if verified && format == TextSignFormat::Minisign {
+            println!(
+                "  trusted comment: {}",
+                minisign_signature_comment(&decoded)?
+            );
+        }
+        if let (true, Some(principal)) = (verified, &self.principal) {
+            println!("  principal: {}", principal);
+        }
+        if let Some(envelope) = envelope {
+            println!("  key id: {}", envelope.key_id);
+            println!("  signed at: {}", envelope.timestamp);
+            if let Some(comment) = envelope.comment {
+                println!("  comment: {}", comment);
+            }
+        }
+        if let Some(diff) = diff {
+            print_manifest_diff(&diff);
+            if mismatched {
+                anyhow::bail!("Directory does not match the manifest");
+            }
+        }
         Ok(())
     }
 }
 
 impl CmdExecutor for KeyGenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let key = process_text_key_generate(self.format)?;
+        let passphrase = self.passphrase.then(|| read_passphrase(true)).transpose()?;
+        let key = process_text_key_generate(self.format, self.key_format, passphrase.as_deref())?;
         for (k, v) in key {
-            tokio::fs::write(self.output_path.join(k), v).await?;
+            let path = self.output_path.join(k);
+            if PUBLIC_KEY_FILES.contains(&k) {
+                tokio::fs::write(path, v).await?;
+            } else {
+                write_private_file(&path, &v)?;
+            }
         }
 
         Ok(())
     }
 }
 
+impl CmdExecutor for TextEncryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+
+        if !self.recipient.is_empty() {
+            let recipients = self
+                .recipient
+                .iter()
+                .map(|r| parse_recipient(r))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return process_age_encrypt(&mut reader, &mut writer, &recipients, self.armor);
+        }
+
+        match &self.key {
+            Some(key) => {
+                let key = get_key(key)?;
+                process_text_encrypt(
+                    &mut reader,
+                    &mut writer,
+                    EncryptionKey::Key(&key),
+                    self.cipher,
+                )
+            }
+            None => {
+                let passphrase = read_passphrase(true)?;
+                let key = EncryptionKey::Passphrase(&passphrase);
+                process_text_encrypt(&mut reader, &mut writer, key, self.cipher)
+            }
+        }
+    }
+}
+
+impl CmdExecutor for TextDecryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        let key = self.key.as_deref().map(get_key).transpose()?;
+        let mut identities = Vec::new();
+        for identity in &self.identity {
+            identities.extend(parse_identities(&get_key(identity)?)?);
+        }
+
+        process_text_decrypt(
+            &mut reader,
+            &mut writer,
+            key.as_deref(),
+            &identities,
+            || read_passphrase(false),
+        )
+    }
+}
+
+impl CmdExecutor for KeyConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = get_key(&self.input)?;
+        let converted = process_key_convert(&key, self.to, self.public)?;
+
+        // 转换后的私钥和生成的私钥一样只允许当前用户读写
+        if !self.public && self.output != "-" {
+            return write_private_file(Path::new(&self.output), &converted);
+        }
+        let mut writer = get_writer(&self.output)?;
+        writer.write_all(&converted)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl CmdExecutor for KeyPasswdOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = tokio::fs::read(&self.input).await?;
+        // 没有被保护的私钥不会询问原来的 passphrase
+        let mut key = process_unprotect_key(&key, || {
+            read_passphrase_from("RCLI_PASSPHRASE", "Current passphrase: ", false)
+        })?;
+        if !self.remove {
+            let passphrase = read_passphrase_from("RCLI_NEW_PASSPHRASE", "New passphrase: ", true)?;
+            key = process_protect_key(&key, &passphrase)?;
+        }
+
+        let output = self.output.as_deref().unwrap_or(&self.input);
+        write_private_file(Path::new(output), &key)
+    }
+}
+
 // impl CmdExecutor for TextSubCommand {
 //     async fn execute(self) -> anyhow::Result<()> {
 //         match self {
@@ -147,3 +823,66 @@ impl CmdExecutor for KeyGenerateOpts {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Opts, SubCommand};
+
+    async fn run(args: &[&str]) -> anyhow::Result<()> {
+        let opts = Opts::try_parse_from([&["rcli", "text"], args].concat())?;
+        let SubCommand::Text(cmd) = opts.cmd else {
+            unreachable!()
+        };
+        cmd.execute().await
+    }
+
+    #[tokio::test]
+    async fn test_verify_dir_after_change() -> anyhow::Result<()> {
+        let root = std::env::temp_dir().join(format!("rcli-text-dir-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dir = root.join("build");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("a.txt"), "hello")?;
+        let dir_arg = dir.to_str().unwrap();
+        let manifest = root.join("build.manifest");
+        let manifest_arg = manifest.to_str().unwrap();
+        let sig = root.join("build.sig");
+        let sig_arg = sig.to_str().unwrap();
+
+        run(&[
+            "sign",
+            "--dir",
+            dir_arg,
+            "--manifest",
+            manifest_arg,
+            "--key",
+            "fixtures/ed25519.signing_key",
+            "--format",
+            "ed25519",
+            "--output",
+            sig_arg,
+        ])
+        .await?;
+        let verify = [
+            "verify",
+            "--dir",
+            dir_arg,
+            "--manifest",
+            manifest_arg,
+            "--key",
+            "fixtures/ed25519.verifying_key",
+            "--format",
+            "ed25519",
+            "--sig",
+            sig_arg,
+        ];
+        run(&verify).await?;
+
+        // manifest 的签名仍然有效，但目录中的文件被改过
+        std::fs::write(dir.join("a.txt"), "hellO")?;
+        assert!(run(&verify).await.is_err());
+        std::fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
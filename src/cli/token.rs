@@ -0,0 +1,103 @@
+use crate::{process_token_generate, process_token_hash, process_token_verify, CmdExecutor};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum TokenSubCommand {
+    #[command(about = "Generate a prefixed API token with an embedded checksum")]
+    Generate(TokenGenerateOpts),
+
+    #[command(about = "Check offline that a token is well-formed")]
+    Verify(TokenVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct TokenGenerateOpts {
+    // 服务前缀，例如 ghp
+    #[arg(short, long)]
+    pub prefix: String,
+
+    // 随机 body 的长度，不包含前缀和 checksum，最少 16 个字符
+    #[arg(short, long, default_value_t = 30)]
+    pub length: u8,
+
+    #[arg(long, default_value = "crc32", value_parser = parse_token_checksum)]
+    pub checksum: TokenChecksum,
+
+    // 同时输出 token 的 blake3 hash，用于存储
+    #[arg(long, default_value_t = false)]
+    pub hash: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct TokenVerifyOpts {
+    pub token: String,
+
+    // 期望的服务前缀，不指定则只校验格式和 checksum
+    #[arg(short, long)]
+    pub prefix: Option<String>,
+
+    #[arg(long, default_value = "crc32", value_parser = parse_token_checksum)]
+    pub checksum: TokenChecksum,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenChecksum {
+    Crc32,
+    Blake3,
+}
+
+fn parse_token_checksum(checksum: &str) -> Result<TokenChecksum, anyhow::Error> {
+    checksum.parse()
+}
+
+impl FromStr for TokenChecksum {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "crc32" => Ok(TokenChecksum::Crc32),
+            "blake3" => Ok(TokenChecksum::Blake3),
+            _ => Err(anyhow::anyhow!("Invalid token checksum: {}", s)),
+        }
+    }
+}
+
+impl From<TokenChecksum> for &'static str {
+    fn from(checksum: TokenChecksum) -> Self {
+        match checksum {
+            TokenChecksum::Crc32 => "crc32",
+            TokenChecksum::Blake3 => "blake3",
+        }
+    }
+}
+
+impl fmt::Display for TokenChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for TokenGenerateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let token = process_token_generate(&self.prefix, self.length, self.checksum)?;
+        println!("{}", token);
+
+        if self.hash {
+            println!("{}", process_token_hash(&token));
+        }
+        Ok(())
+    }
+}
+
+impl CmdExecutor for TokenVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        match process_token_verify(&self.token, self.prefix.as_deref(), self.checksum) {
+            Ok(()) => println!("✓ Token is well-formed"),
+            Err(e) => println!("⚠ Token is malformed: {}", e),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use super::verify_file;
+use crate::{
+    get_reader, get_writer, process_qp_decode, process_qp_encode, process_url_decode,
+    process_url_encode, CmdExecutor,
+};
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum UrlSubCommand {
+    #[command(name = "encode", about = "Percent-encode input for urls and forms")]
+    Encode(UrlEncodeOpts),
+
+    #[command(name = "decode", about = "Decode percent-encoded input")]
+    Decode(UrlDecodeOpts),
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum QpSubCommand {
+    #[command(name = "encode", about = "Encode input to quoted-printable")]
+    Encode(QpEncodeOpts),
+
+    #[command(name = "decode", about = "Decode quoted-printable input")]
+    Decode(QpDecodeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct UrlEncodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // query: query string 中的 key/value，path: path segment，form: x-www-form-urlencoded
+    #[arg(short, long, value_parser = parse_url_mode, default_value = "query")]
+    pub mode: UrlMode,
+}
+
+#[derive(Debug, Parser)]
+pub struct UrlDecodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // form 模式下 '+' 会被解码为空格
+    #[arg(short, long, value_parser = parse_url_mode, default_value = "query")]
+    pub mode: UrlMode,
+}
+
+#[derive(Debug, Parser)]
+pub struct QpEncodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 把输入当作二进制数据，换行也会被编码
+    #[arg(long, default_value_t = false)]
+    pub binary: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct QpDecodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    // 遇到不规范的输入时报错，而不是尽量解码
+    #[arg(long, default_value_t = false)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UrlMode {
+    Query,
+    Path,
+    Form,
+}
+
+fn parse_url_mode(mode: &str) -> Result<UrlMode, anyhow::Error> {
+    mode.parse()
+}
+
+impl FromStr for UrlMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "query" => Ok(UrlMode::Query),
+            "path" => Ok(UrlMode::Path),
+            "form" => Ok(UrlMode::Form),
+            _ => Err(anyhow::anyhow!("Invalid url mode: {}", s)),
+        }
+    }
+}
+
+impl From<UrlMode> for &'static str {
+    fn from(mode: UrlMode) -> Self {
+        match mode {
+            UrlMode::Query => "query",
+            UrlMode::Path => "path",
+            UrlMode::Form => "form",
+        }
+    }
+}
+
+impl fmt::Display for UrlMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for UrlEncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_url_encode(&mut reader, &mut writer, self.mode)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl CmdExecutor for UrlDecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_url_decode(&mut reader, &mut writer, self.mode)
+    }
+}
+
+impl CmdExecutor for QpEncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_qp_encode(&mut reader, &mut writer, self.binary)
+    }
+}
+
+impl CmdExecutor for QpDecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_qp_decode(&mut reader, &mut writer, self.strict)
+    }
+}
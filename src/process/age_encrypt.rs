@@ -0,0 +1,192 @@
+use crate::{decode_signing_key, decode_verifying_key};
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    secrecy::ExposeSecret,
+    x25519::{Identity, Recipient},
+};
+use anyhow::Result;
+use bech32::{ToBase32, Variant};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+};
+
+const RECIPIENT_PREFIX: &str = "age";
+const IDENTITY_PREFIX: &str = "age-secret-key-";
+
+/// 从 ed25519 私钥派生 age 的 X25519 identity，和 ed25519 公钥派生的 recipient 对应
+pub fn x25519_identity(key: &SigningKey) -> Result<Identity> {
+    let encoded = bech32::encode(
+        IDENTITY_PREFIX,
+        key.to_scalar_bytes().to_base32(),
+        Variant::Bech32,
+    )?;
+    encoded
+        .to_uppercase()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid age identity: {}", e))
+}
+
+/// ed25519 公钥转换为 Montgomery 形式就是 X25519 公钥
+pub fn x25519_recipient(key: &VerifyingKey) -> Result<Recipient> {
+    let encoded = bech32::encode(
+        RECIPIENT_PREFIX,
+        key.to_montgomery().to_bytes().to_base32(),
+        Variant::Bech32,
+    )?;
+    encoded
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid age recipient: {}", e))
+}
+
+/// recipient 可以是 age1 开头的公钥、包含 age1 公钥的文件，或者 ed25519 公钥文件
+pub fn parse_recipient(recipient: &str) -> Result<Recipient> {
+    let recipient = recipient.trim();
+    if recipient.starts_with("age1") {
+        return recipient
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid age recipient {}: {}", recipient, e));
+    }
+    if !Path::new(recipient).is_file() {
+        anyhow::bail!(
+            "Invalid recipient {}: not an age1 public key or a key file",
+            recipient
+        );
+    }
+
+    let content = std::fs::read(recipient)?;
+    if let Some(line) = key_lines(&content).find(|line| line.starts_with("age1")) {
+        return parse_recipient(line);
+    }
+    x25519_recipient(&decode_verifying_key(&content)?)
+}
+
+/// identity 文件可以是 age-keygen 生成的文件（支持注释和多个 identity），或者 ed25519 私钥
+pub fn parse_identities(content: &[u8]) -> Result<Vec<Identity>> {
+    let identities = key_lines(content)
+        .filter(|line| line.starts_with("AGE-SECRET-KEY-1"))
+        .map(|line| {
+            line.parse()
+                .map_err(|e| anyhow::anyhow!("Invalid age identity: {}", e))
+        })
+        .collect::<Result<Vec<Identity>>>()?;
+    if !identities.is_empty() {
+        return Ok(identities);
+    }
+
+    Ok(vec![x25519_identity(&decode_signing_key(content)?)?])
+}
+
+/// 生成 age v1 格式的文件，可以用 age/rage 解密
+pub fn process_age_encrypt(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    recipients: &[Recipient],
+    armor: bool,
+) -> Result<()> {
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|r| r as _))?;
+
+    let format = if armor {
+        Format::AsciiArmor
+    } else {
+        Format::Binary
+    };
+    let mut output = encryptor.wrap_output(ArmoredWriter::wrap_output(writer, format)?)?;
+    io::copy(reader, &mut output)?;
+    output.finish()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// 支持 binary 和 ascii armor 格式的 age 文件
+pub fn process_age_decrypt(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    identities: &[Identity],
+) -> Result<()> {
+    if identities.is_empty() {
+        anyhow::bail!("This file was encrypted to recipients, use --identity");
+    }
+
+    let decryptor = age::Decryptor::new(ArmoredReader::new(reader))?;
+    let mut input = decryptor.decrypt(identities.iter().map(|i| i as _))?;
+    io::copy(&mut input, writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 把 identity 序列化为 AGE-SECRET-KEY-1 开头的字符串
+pub fn identity_to_string(identity: &Identity) -> String {
+    identity.to_string().expose_secret().to_string()
+}
+
+fn key_lines(content: &[u8]) -> impl Iterator<Item = &str> {
+    std::str::from_utf8(content)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.signing_key");
+    const VERIFY_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.verifying_key");
+
+    #[test]
+    fn test_x25519_from_ed25519() -> Result<()> {
+        let identity = x25519_identity(&decode_signing_key(SIGNING_KEY)?)?;
+        let recipient = x25519_recipient(&decode_verifying_key(VERIFY_KEY)?)?;
+        assert_eq!(identity.to_public().to_string(), recipient.to_string());
+        assert!(identity_to_string(&identity).starts_with("AGE-SECRET-KEY-1"));
+
+        let content = format!("# created: today\n{}\n", identity_to_string(&identity));
+        let identities = parse_identities(content.as_bytes())?;
+        assert_eq!(identities.len(), 1);
+        assert_eq!(
+            parse_identities(SIGNING_KEY)?[0].to_public().to_string(),
+            recipient.to_string()
+        );
+
+        assert_eq!(
+            parse_recipient("fixtures/ed25519.verifying_key")?.to_string(),
+            recipient.to_string()
+        );
+        assert!(parse_recipient("age1invalid").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_encrypt_decrypt() -> Result<()> {
+        let alice = Identity::generate();
+        let bob = x25519_identity(&decode_signing_key(SIGNING_KEY)?)?;
+        let recipients = [alice.to_public(), bob.to_public()];
+        let data = b"hello age";
+
+        for armor in [false, true] {
+            let mut encrypted = Vec::new();
+            process_age_encrypt(&mut &data[..], &mut encrypted, &recipients, armor)?;
+            assert_eq!(
+                encrypted.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"),
+                armor
+            );
+
+            for identity in [&alice, &bob] {
+                let mut decrypted = Vec::new();
+                process_age_decrypt(
+                    &mut encrypted.as_slice(),
+                    &mut decrypted,
+                    std::slice::from_ref(identity),
+                )?;
+                assert_eq!(decrypted, data);
+            }
+
+            let eve = Identity::generate();
+            let ret = process_age_decrypt(&mut encrypted.as_slice(), &mut Vec::new(), &[eve]);
+            assert!(ret.is_err());
+        }
+        Ok(())
+    }
+}
@@ -1,39 +1,846 @@
-use crate::{get_reader, Base64Format};
+use crate::{Base64Format, Codec};
 use anyhow::Result;
-use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
-use base64::Engine;
-use std::io::Read;
+use base64::engine::general_purpose::{
+    GeneralPurpose, STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD,
+};
+use base64::read::DecoderReader;
+use base64::write::EncoderWriter;
+use data_encoding::{Encoding, BASE32, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
 
-pub fn process_encode(reader: &mut dyn Read, format: Base64Format) -> Result<String> {
-    // let mut reader = get_reader(input)?;
+const BEGIN: &str = "-----BEGIN ";
+const END: &str = "-----END ";
+const DASHES: &str = "-----";
+
+// 分块编解码时每次处理的分组数，每个分组可以独立编解码
+const GROUPS_PER_CHUNK: usize = 1024;
+
+/// 流式编码：边读边写，不会把整个输入读入内存（base58 除外，它需要把整个输入当作一个大整数）
+/// wrap 大于 0 时每 wrap 列换行，armor 指定时输出 `-----BEGIN LABEL-----` / `-----END LABEL-----`
+/// 输出总是以换行结尾
+pub fn process_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: Codec,
+    format: Base64Format,
+    padding: bool,
+    wrap: usize,
+    armor: Option<&str>,
+) -> Result<()> {
+    if let Some(label) = armor {
+        verify_label(label)?;
+        writeln!(writer, "{}{}{}", BEGIN, label, DASHES)?;
+    }
+
+    {
+        let mut wrapper = LineWrapper::new(&mut *writer, wrap);
+        match codec {
+            Codec::Base64 => {
+                let mut encoder = EncoderWriter::new(&mut wrapper, base64_engine(format, padding)?);
+                io::copy(reader, &mut encoder)?;
+
+                // finish 会写出最后不足 3 个字节的部分和 padding
+                encoder.finish()?;
+            }
+            Codec::Base32 => encode_chunked(reader, &mut wrapper, 5, |chunk| {
+                base32_encoding(padding).encode(chunk)
+            })?,
+            Codec::Hex => encode_chunked(reader, &mut wrapper, 1, |chunk| HEXLOWER.encode(chunk))?,
+            Codec::Ascii85 => encode_chunked(reader, &mut wrapper, 4, ascii85_encode)?,
+            Codec::Base58 => {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+                wrapper.write_all(bs58::encode(buf).into_string().as_bytes())?;
+            }
+        }
+        wrapper.end_line()?;
+    }
+
+    if let Some(label) = armor {
+        writeln!(writer, "{}{}{}", END, label, DASHES)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// 流式解码：输出原始字节，图片、压缩包等二进制数据也可以正确解码
+/// 输入中的空白和换行会被忽略，armor 会被自动去掉，指定 label 时会校验 armor 的 label
+pub fn process_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: Codec,
+    format: Base64Format,
+    padding: bool,
+    label: Option<&str>,
+) -> Result<()> {
+    if let (Codec::Base64, Base64Format::Auto) = (codec, format) {
+        return process_decode_auto(reader, writer, label).map(|_| ());
+    }
+
+    let mut reader = SkipWhitespace(dearmor(reader, label)?);
+
+    match codec {
+        Codec::Base64 => {
+            let mut decoder = DecoderReader::new(reader, base64_engine(format, padding)?);
+            io::copy(&mut decoder, writer)?;
+        }
+        Codec::Base32 => decode_chunked(&mut reader, writer, 8, |chunk| {
+            base32_encoding(padding)
+                .decode(chunk)
+                .map_err(|e| anyhow::anyhow!("Invalid base32 input: {}", e))
+        })?,
+        Codec::Hex => decode_chunked(&mut reader, writer, 2, |chunk| {
+            HEXLOWER_PERMISSIVE
+                .decode(chunk)
+                .map_err(|e| anyhow::anyhow!("Invalid hex input: {}", e))
+        })?,
+        Codec::Ascii85 => ascii85_decode(&mut reader, writer)?,
+        Codec::Base58 => {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            writer.write_all(&bs58::decode(buf).into_vec()?)?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// 自动识别 base64 的字母表、padding 和折行，返回识别出来的格式
+/// 识别需要看到完整的输入，所以这里会把输入读入内存
+pub fn process_decode_auto(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    label: Option<&str>,
+) -> Result<Base64Variant> {
     let mut buf = Vec::new();
+    dearmor(reader, label)?.read_to_end(&mut buf)?;
 
-    reader.read_to_end(&mut buf)?;
+    let variant = detect_base64(&buf)?;
+    let engine = base64_engine(variant.format, variant.padded)?;
+    let mut decoder = DecoderReader::new(SkipWhitespace(buf.as_slice()), engine);
+    io::copy(&mut decoder, writer)?;
+    writer.flush()?;
 
-    let encode = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
-    };
+    Ok(variant)
+}
+
+/// 识别出来的 base64 格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Variant {
+    pub format: Base64Format,
+    pub padded: bool,
+    pub line_width: Option<usize>,
+}
 
-    // println!("{}", encode);
-    Ok(encode)
+impl fmt::Display for Base64Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let padding = if self.padded { "padded" } else { "unpadded" };
+        write!(f, "{}, {}", self.format, padding)?;
+        match self.line_width {
+            Some(width) => write!(f, ", wrapped at {} columns", width),
+            None => write!(f, ", not wrapped"),
+        }
+    }
 }
 
-pub fn process_decode(reader: &mut dyn Read, format: Base64Format) -> Result<String> {
-    // let mut reader = get_reader(input)?;
+/// 根据字母表和 padding 判断 base64 的格式，出错时报告第一个非法字符的偏移量
+pub fn detect_base64(input: &[u8]) -> Result<Base64Variant> {
+    let (mut standard_at, mut url_at, mut padding_at) = (None, None, None);
+    let (mut data_len, mut padding_len) = (0, 0);
+    let (mut line_len, mut lines) = (0, Vec::new());
+
+    for (offset, &c) in input.iter().enumerate() {
+        match c {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'+' | b'/' | b'-' | b'_' => {
+                if padding_at.is_some() {
+                    anyhow::bail!(
+                        "Invalid base64 input: data after padding at offset {}",
+                        offset
+                    );
+                }
+
+                match c {
+                    b'+' | b'/' => standard_at = standard_at.or(Some(offset)),
+                    b'-' | b'_' => url_at = url_at.or(Some(offset)),
+                    _ => {}
+                }
+                if standard_at.is_some() && url_at.is_some() {
+                    anyhow::bail!(
+                        "Ambiguous base64 input: mixes standard (+/) and url-safe (-_) characters at offset {}",
+                        offset
+                    );
+                }
+
+                data_len += 1;
+                line_len += 1;
+            }
+            b'=' => {
+                padding_at = padding_at.or(Some(offset));
+                padding_len += 1;
+                line_len += 1;
+                if padding_len > 2 {
+                    anyhow::bail!(
+                        "Invalid base64 input: too much padding at offset {}",
+                        offset
+                    );
+                }
+            }
+            b'\n' => {
+                if line_len > 0 {
+                    lines.push(line_len);
+                }
+                line_len = 0;
+            }
+            c if c.is_ascii_whitespace() => {}
+            _ => anyhow::bail!(
+                "Invalid base64 character {:?} at offset {}",
+                c as char,
+                offset
+            ),
+        }
+    }
+
+    if let Some(offset) = padding_at {
+        if (data_len + padding_len) % 4 != 0 {
+            anyhow::bail!("Invalid base64 input: wrong padding at offset {}", offset);
+        }
+    } else if data_len % 4 == 1 {
+        anyhow::bail!(
+            "Invalid base64 input: {} characters leave a dangling character at the end",
+            data_len
+        );
+    }
 
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
+    if line_len > 0 {
+        lines.push(line_len);
+    }
 
-    let buf = buf.trim();
+    Ok(Base64Variant {
+        // 没有出现 +/ 或者 -_ 时两种字母表解码结果相同，按标准 base64 处理
+        format: if url_at.is_some() {
+            Base64Format::UrlSafe
+        } else {
+            Base64Format::Standard
+        },
+        padded: padding_at.is_some(),
+        line_width: if lines.len() > 1 {
+            Some(lines[0])
+        } else {
+            None
+        },
+    })
+}
+
+/// 标准 base64 默认带 padding，url safe 默认不带 padding，base32 默认带 padding
+pub fn default_padding(codec: Codec, format: Base64Format) -> bool {
+    match codec {
+        Codec::Base64 => !matches!(format, Base64Format::UrlSafe),
+        Codec::Base32 => true,
+        Codec::Base58 | Codec::Hex | Codec::Ascii85 => false,
+    }
+}
 
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
+fn base64_engine(format: Base64Format, padding: bool) -> Result<&'static GeneralPurpose> {
+    let engine = match (format, padding) {
+        (Base64Format::Standard, true) => &STANDARD,
+        (Base64Format::Standard, false) => &STANDARD_NO_PAD,
+        (Base64Format::UrlSafe, true) => &URL_SAFE,
+        (Base64Format::UrlSafe, false) => &URL_SAFE_NO_PAD,
+        (Base64Format::Auto, _) => anyhow::bail!("auto format is only supported when decoding"),
     };
 
-    let decoded = String::from_utf8(decoded)?;
-    // println!("{}", decoded);
+    Ok(engine)
+}
+
+fn base32_encoding(padding: bool) -> Encoding {
+    if padding {
+        BASE32
+    } else {
+        BASE32_NOPAD
+    }
+}
+
+// 每次读取整数个分组，只有最后一块可能不足一个分组，所以 padding 只会出现在结尾
+fn encode_chunked(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    group: usize,
+    encode: impl Fn(&[u8]) -> String,
+) -> Result<()> {
+    let mut buf = vec![0u8; group * GROUPS_PER_CHUNK];
+    loop {
+        let n = read_full(reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(encode(&buf[..n]).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn decode_chunked(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    group: usize,
+    decode: impl Fn(&[u8]) -> Result<Vec<u8>>,
+) -> Result<()> {
+    let mut buf = vec![0u8; group * GROUPS_PER_CHUNK];
+    loop {
+        let n = read_full(reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&decode(&buf[..n])?)?;
+    }
+
+    Ok(())
+}
+
+// 尽量读满 buf，只有遇到 EOF 时才会返回更少的字节
+pub(crate) fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
+// btoa 风格的 ascii85：每 4 个字节编码为 5 个字符，全 0 的分组编码为 z
+fn ascii85_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() / 4 * 5 + 5);
+    for group in data.chunks(4) {
+        let mut bytes = [0u8; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let mut n = u32::from_be_bytes(bytes);
+
+        if n == 0 && group.len() == 4 {
+            out.push('z');
+            continue;
+        }
+
+        let mut chars = [0u8; 5];
+        for c in chars.iter_mut().rev() {
+            *c = (n % 85) as u8 + b'!';
+            n /= 85;
+        }
+        // 不足 4 个字节的分组只输出 len + 1 个字符
+        out.extend(chars[..group.len() + 1].iter().map(|&c| c as char));
+    }
+
+    out
+}
+
+fn ascii85_decode(reader: &mut dyn Read, writer: &mut dyn Write) -> Result<()> {
+    let mut data = Vec::new();
+    let mut buf = vec![0u8; 5 * GROUPS_PER_CHUNK];
+    let mut group = [0u8; 5];
+    let mut len = 0;
+    let mut offset = 0;
+    let mut ended = false;
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+
+        // 兼容 Adobe 风格的 <~ ~> 分隔符，遇到 ~ 就结束
+        let chunk = if offset == 0 {
+            buf[..n].strip_prefix(b"<~").unwrap_or(&buf[..n])
+        } else {
+            &buf[..n]
+        };
+
+        for &c in chunk {
+            match c {
+                b'~' => {
+                    ended = true;
+                    break;
+                }
+                b'z' if len == 0 => data.extend_from_slice(&[0; 4]),
+                b'!'..=b'u' => {
+                    group[len] = c - b'!';
+                    len += 1;
+                    if len == 5 {
+                        data.extend_from_slice(&ascii85_group(&group, offset)?);
+                        len = 0;
+                    }
+                }
+                _ => anyhow::bail!(
+                    "Invalid ascii85 character {:?} at offset {}",
+                    c as char,
+                    offset
+                ),
+            }
+            offset += 1;
+        }
+
+        writer.write_all(&data)?;
+        data.clear();
+        if ended {
+            break;
+        }
+    }
+
+    match len {
+        0 => {}
+        1 => anyhow::bail!("Invalid ascii85 input: dangling character at the end"),
+        _ => {
+            // 不足 5 个字符的分组用 u 补齐，解码后只保留 len - 1 个字节
+            group[len..].fill(b'u' - b'!');
+            writer.write_all(&ascii85_group(&group, offset)?[..len - 1])?;
+        }
+    }
+
+    Ok(())
+}
+
+fn ascii85_group(group: &[u8; 5], offset: usize) -> Result<[u8; 4]> {
+    let n = group
+        .iter()
+        .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d as u32))
+        .ok_or_else(|| anyhow::anyhow!("Invalid ascii85 group before offset {}", offset))?;
+
+    Ok(n.to_be_bytes())
+}
+
+/// 读取时跳过 ascii 空白字符，用来兼容按行折叠的 base64 输入
+struct SkipWhitespace<R>(R);
+
+impl<R: Read> Read for SkipWhitespace<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.0.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+
+            let mut len = 0;
+            for i in 0..n {
+                if !buf[i].is_ascii_whitespace() {
+                    buf[len] = buf[i];
+                    len += 1;
+                }
+            }
+
+            // 整块都是空白时继续读，返回 0 会被当作 EOF
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+/// 每写出 width 个字节插入一个换行，width 为 0 时不换行
+struct LineWrapper<W> {
+    inner: W,
+    width: usize,
+    column: usize,
+}
+
+impl<W: Write> LineWrapper<W> {
+    fn new(inner: W, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            column: 0,
+        }
+    }
+
+    // 最后一行没有换行时补上换行
+    fn end_line(&mut self) -> io::Result<()> {
+        if self.column > 0 {
+            self.inner.write_all(b"\n")?;
+            self.column = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for LineWrapper<W> {
+    // 总是写完整个 buf：EncoderWriter 遇到部分写入时会返回 Ok(0)，导致 io::copy 失败
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.width == 0 {
+            self.inner.write_all(buf)?;
+            self.column += buf.len();
+            return Ok(buf.len());
+        }
+
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+
+            let n = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..n])?;
+            self.column += n;
+            rest = &rest[n..];
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 如果输入以 `-----BEGIN LABEL-----` 开头，返回只包含 armor 内容的 reader，否则原样返回
+fn dearmor<'a>(reader: &'a mut dyn Read, expected: Option<&str>) -> Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+
+    // 跳过开头的空白，再判断是否是 armor
+    loop {
+        let buf = reader.fill_buf()?;
+        let n = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if n == 0 {
+            break;
+        }
+        reader.consume(n);
+    }
+
+    if !reader.fill_buf()?.starts_with(BEGIN.as_bytes()) {
+        if let Some(expected) = expected {
+            anyhow::bail!("Expected armor {} but input is not armored", expected);
+        }
+        return Ok(Box::new(reader));
+    }
+
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let label = parse_armor_line(&header, BEGIN)
+        .ok_or_else(|| anyhow::anyhow!("Invalid armor header: {}", header.trim_end()))?;
+
+    if let Some(expected) = expected {
+        anyhow::ensure!(
+            label == expected,
+            "Armor label mismatch: expected {}, got {}",
+            expected,
+            label
+        );
+    }
+
+    Ok(Box::new(ArmorBody {
+        reader,
+        label: label.to_string(),
+        line: Vec::new(),
+        pos: 0,
+        done: false,
+    }))
+}
+
+fn parse_armor_line<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.trim_end().strip_prefix(prefix)?.strip_suffix(DASHES)
+}
+
+fn verify_label(label: &str) -> Result<()> {
+    anyhow::ensure!(
+        !label.is_empty() && !label.contains('-') && !label.contains(['\r', '\n']),
+        "Invalid armor label: {}",
+        label
+    );
+    Ok(())
+}
+
+/// 逐行读取 armor 的内容，遇到 `-----END LABEL-----` 时结束并校验 label
+struct ArmorBody<R> {
+    reader: R,
+    label: String,
+    line: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Read for ArmorBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.pos < self.line.len() {
+                let n = buf.len().min(self.line.len() - self.pos);
+                buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+
+            if self.done {
+                return Ok(0);
+            }
+
+            self.line.clear();
+            self.pos = 0;
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Missing {}{}{}", END, self.label, DASHES),
+                ));
+            }
+
+            if self.line.starts_with(END.as_bytes()) {
+                let line = String::from_utf8_lossy(&self.line);
+                if parse_armor_line(&line, END) != Some(self.label.as_str()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Armor footer does not match {}: {}",
+                            self.label,
+                            line.trim_end()
+                        ),
+                    ));
+                }
+                self.line.clear();
+                self.done = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_binary() -> Result<()> {
+        let data = (0..=255u8).cycle().take(10_000).collect::<Vec<_>>();
+
+        for format in [Base64Format::Standard, Base64Format::UrlSafe] {
+            let mut encoded = Vec::new();
+            let padding = default_padding(Codec::Base64, format);
+            process_encode(
+                &mut data.as_slice(),
+                &mut encoded,
+                Codec::Base64,
+                format,
+                padding,
+                0,
+                None,
+            )?;
+
+            // 模拟按 76 列折叠的输入
+            let wrapped = encoded
+                .chunks(76)
+                .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+                .collect::<Vec<_>>();
+
+            let mut decoded = Vec::new();
+            process_decode(
+                &mut wrapped.as_slice(),
+                &mut decoded,
+                Codec::Base64,
+                format,
+                padding,
+                None,
+            )?;
+            assert_eq!(decoded, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let mut decoded = Vec::new();
+        let ret = process_decode(
+            &mut "aGVs*G8=".as_bytes(),
+            &mut decoded,
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            None,
+        );
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_armor() -> Result<()> {
+        let data = b"hello world, this line is long enough to be wrapped";
+        let mut encoded = Vec::new();
+        process_encode(
+            &mut data.as_slice(),
+            &mut encoded,
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            16,
+            Some("MESSAGE"),
+        )?;
+
+        let encoded = String::from_utf8(encoded)?;
+        let lines = encoded.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "-----BEGIN MESSAGE-----");
+        assert_eq!(lines[1], "aGVsbG8gd29ybGQs");
+        assert_eq!(lines[lines.len() - 1], "-----END MESSAGE-----");
+        assert!(lines[1..lines.len() - 1].iter().all(|l| l.len() <= 16));
+
+        let mut decoded = Vec::new();
+        let mut reader = encoded.as_bytes();
+        process_decode(
+            &mut reader,
+            &mut decoded,
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            Some("MESSAGE"),
+        )?;
+        assert_eq!(decoded, data);
+
+        // 自动去掉 armor，但是 label 不匹配时报错
+        let mut decoded = Vec::new();
+        let mut reader = encoded.as_bytes();
+        process_decode(
+            &mut reader,
+            &mut decoded,
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            None,
+        )?;
+        assert_eq!(decoded, data);
+
+        let mut reader = encoded.as_bytes();
+        let ret = process_decode(
+            &mut reader,
+            &mut Vec::new(),
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            Some("KEY"),
+        );
+        assert!(ret.is_err());
+
+        let broken = encoded.replace("END MESSAGE", "END KEY");
+        let ret = process_decode(
+            &mut broken.as_bytes(),
+            &mut Vec::new(),
+            Codec::Base64,
+            Base64Format::Standard,
+            true,
+            None,
+        );
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_codecs_roundtrip() -> Result<()> {
+        let data = (0..=255u8).cycle().take(10_007).collect::<Vec<_>>();
+        let cases = [
+            (Codec::Base64, false),
+            (Codec::Base32, true),
+            (Codec::Base32, false),
+            (Codec::Base58, false),
+            (Codec::Hex, false),
+            (Codec::Ascii85, false),
+        ];
+
+        for (codec, padding) in cases {
+            let mut encoded = Vec::new();
+            let format = Base64Format::UrlSafe;
+            process_encode(
+                &mut data.as_slice(),
+                &mut encoded,
+                codec,
+                format,
+                padding,
+                76,
+                None,
+            )?;
+
+            let mut decoded = Vec::new();
+            process_decode(
+                &mut encoded.as_slice(),
+                &mut decoded,
+                codec,
+                format,
+                padding,
+                None,
+            )?;
+            assert_eq!(decoded, data, "{} roundtrip", codec);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_codecs_known_values() -> Result<()> {
+        let encode = |codec, padding, data: &[u8]| -> Result<String> {
+            let mut out = Vec::new();
+            let mut reader = data;
+            process_encode(
+                &mut reader,
+                &mut out,
+                codec,
+                Base64Format::Standard,
+                padding,
+                0,
+                None,
+            )?;
+            Ok(String::from_utf8(out)?.trim_end().to_string())
+        };
+
+        assert_eq!(encode(Codec::Base64, false, b"hello")?, "aGVsbG8");
+        assert_eq!(encode(Codec::Base32, true, b"hello")?, "NBSWY3DP");
+        assert_eq!(encode(Codec::Base32, true, b"hi")?, "NBUQ====");
+        assert_eq!(encode(Codec::Base32, false, b"hi")?, "NBUQ");
+        assert_eq!(
+            encode(Codec::Base58, false, b"hello world")?,
+            "StV1DL6CwTryKyV"
+        );
+        assert_eq!(encode(Codec::Hex, false, b"hello")?, "68656c6c6f");
+        assert_eq!(
+            encode(Codec::Ascii85, false, b"hello world")?,
+            "BOu!rD]j7BEbo7"
+        );
+        assert_eq!(encode(Codec::Ascii85, false, &[0, 0, 0, 0, 1])?, "z!<");
+
+        let mut decoded = Vec::new();
+        let mut reader = "<~BOu!rD]j7BEbo7~>".as_bytes();
+        let format = Base64Format::Standard;
+        process_decode(
+            &mut reader,
+            &mut decoded,
+            Codec::Ascii85,
+            format,
+            false,
+            None,
+        )?;
+        assert_eq!(decoded, b"hello world");
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_base64() -> Result<()> {
+        let variant = detect_base64(b"aGk=")?;
+        assert_eq!(variant.format, Base64Format::Standard);
+        assert!(variant.padded);
+
+        let variant = detect_base64(b"-_8\n-_8\n-_")?;
+        assert_eq!(variant.format, Base64Format::UrlSafe);
+        assert!(!variant.padded);
+        assert_eq!(variant.line_width, Some(3));
+
+        let err = detect_base64(b"ab+/ab-_").unwrap_err();
+        assert!(err.to_string().contains("offset 6"), "{}", err);
+
+        let err = detect_base64(b"aGVs\nbG8*").unwrap_err();
+        assert!(err.to_string().contains("offset 8"), "{}", err);
+
+        assert!(detect_base64(b"aGk=aGk=").is_err());
+        assert!(detect_base64(b"aGVsb").is_err());
 
-    Ok(decoded)
+        let mut decoded = Vec::new();
+        let mut reader = "-_8\n".as_bytes();
+        let variant = process_decode_auto(&mut reader, &mut decoded, None)?;
+        assert_eq!(variant.to_string(), "url, unpadded, not wrapped");
+        assert_eq!(decoded, [0xfb, 0xff]);
+        Ok(())
+    }
 }
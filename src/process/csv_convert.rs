@@ -2,6 +2,7 @@ use crate::OutputFormat;
 use anyhow::Result;
 use csv::Reader;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 
 /// deserialize 是反序列化，它是用来将 json 格式的数据转换为 struct 的
@@ -44,16 +45,55 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<
         ret.push(json_value);
     }
 
-    // to_string_pretty 是用来将 struct 转换为 json 格式的，它是用来将 struct 转换为 json 格式的
-
-    let content = match format {
-        // 将 数据结构 ret 序列化为 json 格式
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
-    };
+    let content = serialize_records(&ret, format)?;
 
     // fs::write 是用来将数据写入到文件中的，它是用来将数据写入到文件中的
     fs::write(output, content)?;
 
     Ok(())
 }
+
+/// 将一组记录序列化为指定的格式，csv 和 genpass 等子命令共用
+/// csv 格式下，每条记录必须是一个 struct 或者 map，第一条记录的字段名作为 header
+pub fn serialize_records<T: Serialize>(records: &[T], format: OutputFormat) -> Result<String> {
+    // to_string_pretty 是用来将 struct 转换为 json 格式的，它是用来将 struct 转换为 json 格式的
+    let content = match format {
+        // 将 数据结构 records 序列化为 json 格式
+        OutputFormat::Json => serde_json::to_string_pretty(records)?,
+        OutputFormat::Yaml => serde_yaml::to_string(records)?,
+        OutputFormat::Csv => to_csv(records)?,
+    };
+
+    Ok(content)
+}
+
+fn to_csv<T: Serialize>(records: &[T]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut headers: Option<Vec<String>> = None;
+
+    for record in records {
+        // 先统一转为 json value，这样 struct 和 map 都可以按字段写出
+        let Value::Object(fields) = serde_json::to_value(record)? else {
+            anyhow::bail!("Only structs or maps can be written as csv records");
+        };
+
+        if headers.is_none() {
+            let keys: Vec<String> = fields.keys().cloned().collect();
+            writer.write_record(&keys)?;
+            headers = Some(keys);
+        }
+
+        let row = headers
+            .iter()
+            .flatten()
+            .map(|key| match fields.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(v) => v.to_string(),
+            })
+            .collect::<Vec<_>>();
+        writer.write_record(&row)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
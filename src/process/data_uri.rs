@@ -0,0 +1,96 @@
+use crate::process_decode_auto;
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use percent_encoding::percent_decode_str;
+use std::io::Write;
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+/// 生成 `data:<mime>;base64,<payload>`，没有指定 mime 时根据内容和文件名判断
+pub fn process_datauri_encode(data: &[u8], path: Option<&str>, mime: Option<&str>) -> String {
+    let mime = match mime {
+        Some(mime) => mime.to_string(),
+        None => sniff_mime(data, path),
+    };
+
+    format!("data:{};base64,{}", mime, STANDARD.encode(data))
+}
+
+/// 解析 data URI，把 payload 写入 writer，返回声明的 MIME 类型
+pub fn process_datauri_decode(uri: &str, writer: &mut dyn Write) -> Result<String> {
+    let rest = uri
+        .trim()
+        .strip_prefix("data:")
+        .ok_or_else(|| anyhow::anyhow!("Invalid data URI: missing data: scheme"))?;
+    let (meta, payload) = rest
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("Invalid data URI: missing ',' before the payload"))?;
+
+    let (media_type, is_base64) = match meta.strip_suffix(";base64") {
+        Some(media_type) => (media_type, true),
+        None => (meta, false),
+    };
+
+    if is_base64 {
+        process_decode_auto(&mut payload.as_bytes(), writer, None)?;
+    } else {
+        // 非 base64 的 payload 是 percent-encoding 编码的
+        writer.write_all(&percent_decode_str(payload).collect::<Vec<_>>())?;
+        writer.flush()?;
+    }
+
+    // RFC 2397: 省略 media type 时默认为 text/plain;charset=US-ASCII
+    let mime = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_string()
+    } else {
+        media_type.to_string()
+    };
+
+    Ok(mime)
+}
+
+/// 先根据 magic bytes 判断，识别不出来时再根据扩展名判断
+pub fn sniff_mime(data: &[u8], path: Option<&str>) -> String {
+    infer::get(data)
+        .map(|kind| kind.mime_type().to_string())
+        .or_else(|| path.and_then(|p| mime_guess::from_path(p).first_raw().map(String::from)))
+        .unwrap_or_else(|| DEFAULT_MIME.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR";
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(PNG_HEADER, Some("logo.bin")), "image/png");
+        assert_eq!(
+            sniff_mime(b"<svg></svg>", Some("logo.svg")),
+            "image/svg+xml"
+        );
+        assert_eq!(sniff_mime(b"body {}", Some("style.css")), "text/css");
+        assert_eq!(sniff_mime(b"???", None), DEFAULT_MIME);
+    }
+
+    #[test]
+    fn test_datauri_roundtrip() -> Result<()> {
+        let uri = process_datauri_encode(PNG_HEADER, None, None);
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+
+        let mut decoded = Vec::new();
+        let mime = process_datauri_decode(&uri, &mut decoded)?;
+        assert_eq!(mime, "image/png");
+        assert_eq!(decoded, PNG_HEADER);
+
+        let mut decoded = Vec::new();
+        let mime = process_datauri_decode("data:,hello%20world", &mut decoded)?;
+        assert_eq!(mime, "text/plain;charset=US-ASCII");
+        assert_eq!(decoded, b"hello world");
+
+        assert!(process_datauri_decode("hello", &mut Vec::new()).is_err());
+        Ok(())
+    }
+}
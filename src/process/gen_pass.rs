@@ -1,4 +1,8 @@
+use crate::BreachDb;
+use anyhow::Result;
 use rand::seq::SliceRandom;
+use serde::Serialize;
+use zxcvbn::zxcvbn;
 
 // 常量，需要标注类型，否则编译器无法识别
 // b 放在字符串前面，会将字符串转换为字节数组
@@ -63,6 +67,90 @@ pub fn process_genpass(
     Ok(password)
 }
 
+/// 批量生成密码时的一条记录，可以被序列化为 json / yaml / csv
+#[derive(Debug, Serialize)]
+pub struct PasswordRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    pub password: String,
+    pub length: usize,
+    pub charset: String,
+    pub entropy_bits: f64,
+    pub score: u8,
+}
+
+/// 为每个 account 生成一个密码，account 为 None 时不输出 account 列
+/// 指定了泄露库时，命中泄露库的密码会被丢弃并重新生成
+pub fn process_genpass_batch(
+    accounts: Vec<Option<String>>,
+    length: u8,
+    upper: bool,
+    lower: bool,
+    number: bool,
+    symbol: bool,
+    mut breach_db: Option<&mut BreachDb>,
+) -> Result<Vec<PasswordRecord>> {
+    let (charset, pool_size) = charset_summary(upper, lower, number, symbol);
+    // 每个字符从 pool 中随机选取，熵 = 长度 * log2(pool 大小)
+    let entropy_bits = (length as f64 * (pool_size as f64).log2() * 100.0).round() / 100.0;
+
+    accounts
+        .into_iter()
+        .map(|account| {
+            let generate = || process_genpass(length, upper, lower, number, symbol);
+            let password = match breach_db.as_deref_mut() {
+                Some(db) => db.reject_breached(generate)?,
+                None => generate()?,
+            };
+            let score = zxcvbn(&password, &[])?.score();
+            Ok(PasswordRecord {
+                account,
+                length: password.len(),
+                password,
+                charset: charset.clone(),
+                entropy_bits,
+                score,
+            })
+        })
+        .collect()
+}
+
+/// 从 csv 文件中读取指定列，作为 account 名称
+pub fn read_accounts(input: &str, column: &str) -> Result<Vec<String>> {
+    let mut reader = csv::Reader::from_path(input)?;
+    let index = reader
+        .headers()?
+        .iter()
+        .position(|h| h == column)
+        .ok_or_else(|| anyhow::anyhow!("Column {} not found in {}", column, input))?;
+
+    let mut accounts = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        accounts.push(record.get(index).unwrap_or_default().to_string());
+    }
+
+    Ok(accounts)
+}
+
+fn charset_summary(upper: bool, lower: bool, number: bool, symbol: bool) -> (String, usize) {
+    let sets = [
+        (upper, "upper", UPPER),
+        (lower, "lower", LOWER),
+        (number, "number", NUMBER),
+        (symbol, "symbol", SYMBOL),
+    ];
+
+    let enabled = sets.iter().filter(|(on, _, _)| *on);
+    let names = enabled
+        .clone()
+        .map(|(_, name, _)| *name)
+        .collect::<Vec<_>>();
+    let size = enabled.map(|(_, _, chars)| chars.len()).sum();
+
+    (names.join("+"), size)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +171,18 @@ mod tests {
         let message = b"";
         let result = message.choose(&mut rng).expect("message is empty");
     }
+
+    #[test]
+    fn test_genpass_batch() -> Result<()> {
+        let accounts = vec![Some("alice".to_string()), Some("bob".to_string())];
+        let records = process_genpass_batch(accounts, 20, true, true, true, false, None)?;
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].account.as_deref(), Some("alice"));
+        assert_eq!(records[1].password.len(), 20);
+        assert_eq!(records[1].charset, "upper+lower+number");
+        // 24 + 25 + 9 = 58 个字符，20 * log2(58) ≈ 117.16
+        assert_eq!(records[1].entropy_bits, 117.16);
+        Ok(())
+    }
 }
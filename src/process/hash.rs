@@ -0,0 +1,208 @@
+use crate::{get_reader, HashAlgorithm};
+use anyhow::Result;
+use data_encoding::HEXLOWER;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::io::{self, Read, Write};
+
+/// --check 中每个文件的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Ok,
+    Failed,
+    // 文件不存在或者读取失败
+    Unreadable,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChecksumCheck {
+    pub path: String,
+    pub status: ChecksumStatus,
+}
+
+/// 流式计算 digest，返回小写 hex，和 blake3 签名一样不需要把输入读到内存里
+pub fn process_hash(reader: &mut dyn Read, algorithm: HashAlgorithm) -> Result<String> {
+    let digest = match algorithm {
+        HashAlgorithm::Blake3 => blake3::Hasher::new()
+            .update_reader(reader)?
+            .finalize()
+            .as_bytes()
+            .to_vec(),
+        HashAlgorithm::Sha256 => digest::<Sha256>(reader)?,
+        HashAlgorithm::Sha512 => digest::<Sha512>(reader)?,
+        HashAlgorithm::Sha1 => digest::<Sha1>(reader)?,
+    };
+
+    Ok(HEXLOWER.encode(&digest))
+}
+
+/// coreutils 格式的一行：`<hex>  <path>`，路径包含 \ 或换行时转义并在行首加 \
+pub fn format_checksum_line(hash: &str, path: &str) -> String {
+    if path.contains(['\\', '\n', '\r']) {
+        let escaped = path
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        format!("\\{}  {}", hash, escaped)
+    } else {
+        format!("{}  {}", hash, path)
+    }
+}
+
+/// 解析 `<hex>  <path>` 或者 binary 模式的 `<hex> *<path>`，返回 hash 和路径
+pub fn parse_checksum_line(line: &str, algorithm: HashAlgorithm) -> Option<(String, String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (hash, rest) = line.split_at_checked(digest_len(algorithm) * 2)?;
+    if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let path = rest
+        .strip_prefix("  ")
+        .or_else(|| rest.strip_prefix(" *"))
+        .filter(|p| !p.is_empty())?;
+    let path = if escaped {
+        unescape(path)?
+    } else {
+        path.to_string()
+    };
+
+    Some((hash.to_lowercase(), path))
+}
+
+/// 校验 checksum 列表，返回每个文件的结果和格式不正确的行数，文件路径相对于当前目录
+pub fn process_hash_check(content: &str, algorithm: HashAlgorithm) -> (Vec<ChecksumCheck>, usize) {
+    let mut checks = Vec::new();
+    let mut malformed = 0;
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let Some((expected, path)) = parse_checksum_line(line, algorithm) else {
+            malformed += 1;
+            continue;
+        };
+        let status = match get_reader(&path).and_then(|mut r| process_hash(&mut r, algorithm)) {
+            Ok(hash) if hash == expected => ChecksumStatus::Ok,
+            Ok(_) => ChecksumStatus::Failed,
+            Err(_) => ChecksumStatus::Unreadable,
+        };
+        checks.push(ChecksumCheck { path, status });
+    }
+
+    (checks, malformed)
+}
+
+fn digest<D: Digest + Write>(reader: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut hasher = D::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+fn digest_len(algorithm: HashAlgorithm) -> usize {
+    match algorithm {
+        HashAlgorithm::Blake3 | HashAlgorithm::Sha256 => 32,
+        HashAlgorithm::Sha512 => 64,
+        HashAlgorithm::Sha1 => 20,
+    }
+}
+
+fn unescape(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_hash() -> Result<()> {
+        // 和 sha256sum、sha1sum、b3sum 的输出一致
+        let cases = [
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(process_hash(&mut &b"abc"[..], algorithm)?, expected);
+        }
+        assert_eq!(
+            process_hash(&mut &b""[..], HashAlgorithm::Sha512)?.len(),
+            128
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_line() {
+        let hash = "a9993e364706816aba3e25717850c26c9cd0d89d";
+        let line = format_checksum_line(hash, "a b.txt");
+        assert_eq!(line, format!("{}  a b.txt", hash));
+        assert_eq!(
+            parse_checksum_line(&line, HashAlgorithm::Sha1),
+            Some((hash.to_string(), "a b.txt".to_string()))
+        );
+
+        let line = format_checksum_line(hash, "a\\b\nc");
+        assert!(line.starts_with('\\'));
+        assert_eq!(
+            parse_checksum_line(&line, HashAlgorithm::Sha1),
+            Some((hash.to_string(), "a\\b\nc".to_string()))
+        );
+
+        let binary = format!("{} *file.bin", hash.to_uppercase());
+        assert_eq!(
+            parse_checksum_line(&binary, HashAlgorithm::Sha1),
+            Some((hash.to_string(), "file.bin".to_string()))
+        );
+        assert_eq!(parse_checksum_line(&line, HashAlgorithm::Sha256), None);
+        assert_eq!(
+            parse_checksum_line("hello  world", HashAlgorithm::Sha1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_process_hash_check() -> Result<()> {
+        let hash = process_hash(&mut get_reader("Cargo.toml")?, HashAlgorithm::Sha256)?;
+        let wrong = HEXLOWER.encode(&[0u8; 32]);
+        let content = format!(
+            "{}  Cargo.toml\n{} *Cargo.toml\n{}  not-exist\nbad line\n",
+            hash, wrong, hash
+        );
+        let (checks, malformed) = process_hash_check(&content, HashAlgorithm::Sha256);
+        let status = checks.iter().map(|c| c.status).collect::<Vec<_>>();
+        assert_eq!(
+            status,
+            [
+                ChecksumStatus::Ok,
+                ChecksumStatus::Failed,
+                ChecksumStatus::Unreadable
+            ]
+        );
+        assert_eq!(malformed, 1);
+        Ok(())
+    }
+}
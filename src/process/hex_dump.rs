@@ -0,0 +1,203 @@
+use super::b64::read_full;
+use anyhow::Result;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// xxd 风格的输出：`offset: hex  ascii`，每行 cols 个字节，每 group 个字节一组
+/// 偏移从 seek 开始计算，length 限制最多输出的字节数
+pub fn process_hex_dump(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    seek: u64,
+    length: Option<u64>,
+    cols: usize,
+    group: usize,
+) -> Result<()> {
+    if cols == 0 {
+        anyhow::bail!("Hex dump width must be greater than 0");
+    }
+
+    // reader 不一定支持 Seek，例如 stdin，直接丢弃前 seek 个字节
+    let skipped = io::copy(&mut reader.take(seek), &mut io::sink())?;
+    if skipped < seek {
+        anyhow::bail!("Cannot seek to {}: input is only {} bytes", seek, skipped);
+    }
+
+    let mut reader = reader.take(length.unwrap_or(u64::MAX));
+    let mut buf = vec![0u8; cols];
+    let mut offset = seek;
+    // 最后一行不足 cols 时用空格补齐，保证 ascii 列对齐
+    let hex_width = hex_column_width(cols, group);
+
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let line = &buf[..n];
+
+        let mut hex = String::with_capacity(hex_width);
+        for (i, b) in line.iter().enumerate() {
+            if i > 0 && group > 0 && i % group == 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x}", b));
+        }
+        let ascii = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+
+        writeln!(
+            writer,
+            "{:08x}: {:<width$}  {}",
+            offset,
+            hex,
+            ascii,
+            width = hex_width
+        )?;
+
+        offset += n as u64;
+        if n < cols {
+            break;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// 把 hex dump 还原为二进制数据，只解析每行 ':' 之后、两个连续空格之前的 hex 部分
+/// 偏移以第一行为基准，中间缺失的部分用 0 填充
+pub fn process_hex_reverse(reader: &mut dyn Read, writer: &mut dyn Write) -> Result<()> {
+    let reader = BufReader::new(reader);
+    let mut base = None;
+    let mut written = 0u64;
+
+    for (lineno, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (offset, rest) = line.split_once(':').ok_or_else(|| {
+            anyhow::anyhow!("Invalid hex dump at line {}: missing ':'", lineno + 1)
+        })?;
+        let offset = u64::from_str_radix(offset.trim(), 16).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid hex dump at line {}: bad offset {}",
+                lineno + 1,
+                offset
+            )
+        })?;
+
+        let base = *base.get_or_insert(offset);
+        let position = offset
+            .checked_sub(base)
+            .filter(|position| *position >= written)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid hex dump at line {}: offset goes backwards",
+                    lineno + 1
+                )
+            })?;
+        io::copy(&mut io::repeat(0).take(position - written), writer)?;
+        written = position;
+
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        let hex = rest.split("  ").next().unwrap_or_default();
+        let digits = hex.bytes().filter(|b| *b != b' ').collect::<Vec<_>>();
+        if digits.len() % 2 != 0 {
+            anyhow::bail!(
+                "Invalid hex dump at line {}: odd number of hex digits",
+                lineno + 1
+            );
+        }
+
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|s| u8::from_str_radix(s, 16).ok())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid hex dump at line {}: bad hex digit", lineno + 1)
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        writer.write_all(&bytes)?;
+        written += bytes.len() as u64;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn hex_column_width(cols: usize, group: usize) -> usize {
+    // group 为 0 时不分组，没有分隔的空格
+    cols * 2 + (cols - 1).checked_div(group).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(
+        data: &[u8],
+        seek: u64,
+        length: Option<u64>,
+        cols: usize,
+        group: usize,
+    ) -> Result<String> {
+        let mut out = Vec::new();
+        process_hex_dump(&mut &data[..], &mut out, seek, length, cols, group)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_hex_dump() -> Result<()> {
+        let data = b"Hello, rcli!\n\x00\x01\x02\xff";
+
+        assert_eq!(
+            dump(data, 0, None, 16, 2)?,
+            "00000000: 4865 6c6c 6f2c 2072 636c 6921 0a00 0102  Hello, rcli!....\n\
+             00000010: ff                                       .\n"
+        );
+        assert_eq!(
+            dump(data, 7, Some(5), 4, 1)?,
+            "00000007: 72 63 6c 69  rcli\n0000000b: 21           !\n"
+        );
+        assert_eq!(
+            dump(data, 0, Some(3), 8, 0)?,
+            "00000000: 48656c            Hel\n"
+        );
+        assert!(dump(data, 100, None, 16, 2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_reverse() -> Result<()> {
+        let data = (0..=255u8)
+            .chain(b"  two  spaces".iter().copied())
+            .collect::<Vec<_>>();
+
+        for (cols, group) in [(16, 2), (7, 3), (32, 0)] {
+            let text = dump(&data, 0, None, cols, group)?;
+            let mut out = Vec::new();
+            process_hex_reverse(&mut text.as_bytes(), &mut out)?;
+            assert_eq!(out, data);
+        }
+
+        // 以第一行为基准，缺失的偏移补 0
+        let mut out = Vec::new();
+        process_hex_reverse(&mut "00000010: 0102\n00000014: ff\n".as_bytes(), &mut out)?;
+        assert_eq!(out, [1, 2, 0, 0, 0xff]);
+
+        assert!(process_hex_reverse(&mut "00000000: 0g\n".as_bytes(), &mut Vec::new()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::IdKind;
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ulid::Ulid;
+use uuid::Uuid;
+
+/// 解析出来的 id 信息
+#[derive(Debug)]
+pub struct IdInfo {
+    pub kind: &'static str,
+    pub version: Option<usize>,
+    pub timestamp: Option<SystemTime>,
+}
+
+/// 生成 count 个 id，sort 为 true 时按 id 中嵌入的时间排序
+pub fn process_id_generate(
+    kind: IdKind,
+    count: usize,
+    alphabet: &str,
+    length: usize,
+    sort: bool,
+) -> Result<Vec<String>> {
+    if sort && !matches!(kind, IdKind::Uuid7 | IdKind::Ulid) {
+        anyhow::bail!("Only time-ordered ids (uuid7, ulid) can be sorted by time");
+    }
+
+    let alphabet = alphabet.chars().collect::<Vec<_>>();
+    if matches!(kind, IdKind::Nanoid) && alphabet.is_empty() {
+        anyhow::bail!("nanoid alphabet must not be empty");
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut ids = (0..count)
+        .map(|_| match kind {
+            IdKind::Uuid4 => Uuid::new_v4().to_string(),
+            IdKind::Uuid7 => Uuid::now_v7().to_string(),
+            IdKind::Ulid => Ulid::new().to_string(),
+            IdKind::Nanoid => (0..length)
+                .map(|_| *alphabet.choose(&mut rng).expect("alphabet won't be empty"))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    if sort {
+        // uuid7 和 ulid 的字符串顺序就是时间顺序，先按时间，同一毫秒内再按字符串排序
+        ids.sort_by_cached_key(|id| {
+            (
+                process_id_inspect(id).ok().and_then(|i| i.timestamp),
+                id.clone(),
+            )
+        });
+    }
+
+    Ok(ids)
+}
+
+/// 识别 uuid 或 ulid，返回版本号和其中嵌入的时间
+pub fn process_id_inspect(id: &str) -> Result<IdInfo> {
+    let id = id.trim();
+
+    if let Ok(uuid) = Uuid::try_parse(id) {
+        let timestamp = uuid.get_timestamp().map(|ts| {
+            let (secs, nanos) = ts.to_unix();
+            UNIX_EPOCH + Duration::new(secs, nanos)
+        });
+
+        return Ok(IdInfo {
+            kind: "uuid",
+            version: Some(uuid.get_version_num()),
+            timestamp,
+        });
+    }
+
+    if let Ok(ulid) = Ulid::from_string(id) {
+        return Ok(IdInfo {
+            kind: "ulid",
+            version: None,
+            timestamp: Some(ulid.datetime()),
+        });
+    }
+
+    anyhow::bail!("{} is neither a valid uuid nor a valid ulid", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_generate() -> Result<()> {
+        let ids = process_id_generate(IdKind::Nanoid, 5, "abc", 10, false)?;
+        assert_eq!(ids.len(), 5);
+        assert!(ids
+            .iter()
+            .all(|id| id.len() == 10 && id.chars().all(|c| "abc".contains(c))));
+
+        let ids = process_id_generate(IdKind::Uuid7, 20, "", 0, true)?;
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+
+        assert!(process_id_generate(IdKind::Uuid4, 2, "", 0, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_id_inspect() -> Result<()> {
+        // RFC 9562 Appendix A.6 的 uuid v7 示例，时间为 2022-02-22T19:22:22Z
+        let info = process_id_inspect("017F22E2-79B0-7CC3-98C4-DC0C0C07398F")?;
+        assert_eq!(info.kind, "uuid");
+        assert_eq!(info.version, Some(7));
+        assert_eq!(
+            info.timestamp,
+            Some(UNIX_EPOCH + Duration::from_millis(1645557742000))
+        );
+
+        let info = process_id_inspect("01ARZ3NDEKTSV4RRFFQ69G5FAV")?;
+        assert_eq!(info.kind, "ulid");
+        assert_eq!(
+            info.timestamp,
+            Some(UNIX_EPOCH + Duration::from_millis(1469922850259))
+        );
+
+        let info = process_id_inspect(&Uuid::new_v4().to_string())?;
+        assert_eq!(info.version, Some(4));
+        assert_eq!(info.timestamp, None);
+
+        assert!(process_id_inspect("not-an-id").is_err());
+        Ok(())
+    }
+}
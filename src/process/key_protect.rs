@@ -0,0 +1,70 @@
+use super::text_encrypt::MAGIC;
+use crate::{process_text_decrypt, process_text_encrypt, EncryptionKey, TextCipher};
+use anyhow::Result;
+
+/// 被保护的私钥就是用 passphrase 加密的 text encrypt 文件，内容是原始的密钥文件
+pub fn is_protected_key(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 使用 Argon2id 派生的密钥和 XChaCha20-Poly1305 加密私钥文件
+pub fn process_protect_key(key: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if is_protected_key(key) {
+        anyhow::bail!("Key file is already protected with a passphrase");
+    }
+
+    let mut protected = Vec::new();
+    process_text_encrypt(
+        &mut &key[..],
+        &mut protected,
+        EncryptionKey::Passphrase(passphrase),
+        TextCipher::XChaCha20Poly1305,
+    )?;
+    Ok(protected)
+}
+
+/// 没有被保护的密钥原样返回，不会调用 passphrase
+pub fn process_unprotect_key(
+    data: &[u8],
+    passphrase: impl FnOnce() -> Result<String>,
+) -> Result<Vec<u8>> {
+    if !is_protected_key(data) {
+        return Ok(data.to_vec());
+    }
+
+    let mut key = Vec::new();
+    process_text_decrypt(&mut &data[..], &mut key, None, &[], passphrase)
+        .map_err(|e| anyhow::anyhow!("Failed to unlock key file: {}", e))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_signing_key;
+
+    const SIGNING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.signing_key");
+
+    fn passphrase(p: &str) -> impl FnOnce() -> Result<String> + '_ {
+        move || Ok(p.to_string())
+    }
+
+    #[test]
+    fn test_protect_unprotect_key() -> Result<()> {
+        let protected = process_protect_key(SIGNING_KEY, "secret")?;
+        assert!(is_protected_key(&protected));
+        assert!(decode_signing_key(&protected).is_err());
+        assert!(process_protect_key(&protected, "secret").is_err());
+
+        assert_eq!(
+            process_unprotect_key(&protected, passphrase("secret"))?,
+            SIGNING_KEY
+        );
+        assert!(process_unprotect_key(&protected, passphrase("wrong")).is_err());
+
+        // 明文密钥不需要 passphrase
+        let key = process_unprotect_key(SIGNING_KEY, || anyhow::bail!("not called"))?;
+        assert_eq!(key, SIGNING_KEY);
+        Ok(())
+    }
+}
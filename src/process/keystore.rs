@@ -0,0 +1,354 @@
+use crate::{
+    decode_signing_key, decode_verifying_key, encode_signing_key, is_public_key, key_fingerprint,
+    process_unprotect_key, write_private_file, KeyFormat,
+};
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// 目录结构：
+// <name>.json 记录所有版本的公钥，轮换后旧的公钥仍然保留，用来验证以前的签名
+// <name>.key  当前版本的私钥，可能被 passphrase 保护，只导入公钥时不存在
+const MIN_KEY_ID_PREFIX: usize = 4;
+
+/// keystore 中的一个 ed25519 密钥，versions 按创建时间排序，最后一个是当前版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyEntry {
+    pub name: String,
+    pub versions: Vec<KeyVersion>,
+    #[serde(skip)]
+    pub has_private: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyVersion {
+    pub key_id: String,
+    pub public_key: String,
+    pub created: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retired: Option<String>,
+}
+
+impl KeyEntry {
+    pub fn current(&self) -> &KeyVersion {
+        self.versions
+            .last()
+            .expect("key entry has at least one version")
+    }
+
+    pub fn version(&self, key_id: &str) -> Option<&KeyVersion> {
+        self.versions.iter().find(|v| v.key_id == key_id)
+    }
+}
+
+impl KeyVersion {
+    fn new(public: &[u8]) -> Self {
+        Self {
+            key_id: key_fingerprint(public),
+            public_key: URL_SAFE_NO_PAD.encode(public),
+            created: now(),
+            retired: None,
+        }
+    }
+
+    /// 32 字节的 raw 公钥
+    pub fn public_key(&self) -> Result<Vec<u8>> {
+        Ok(URL_SAFE_NO_PAD.decode(&self.public_key)?)
+    }
+}
+
+/// 优先使用 RCLI_KEYSTORE，其次是 $XDG_DATA_HOME/rcli/keys，最后是 ~/.local/share/rcli/keys
+pub fn keystore_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("RCLI_KEYSTORE") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir).join("rcli").join("keys"));
+    }
+    let home = std::env::var_os("HOME")
+        .ok_or_else(|| anyhow::anyhow!("Cannot find the keystore: HOME is not set"))?;
+    Ok(PathBuf::from(home).join(".local/share/rcli/keys"))
+}
+
+pub fn process_key_list(store: &Path) -> Result<Vec<KeyEntry>> {
+    if !store.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for file in fs::read_dir(store)? {
+        let path = file?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                entries.push(read_entry(store, name)?);
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(entries)
+}
+
+/// 导入私钥或者只导入公钥，私钥按原样保存，被 passphrase 保护的私钥仍然是加密的
+/// passphrase 只在私钥被保护时用来导出公钥
+pub fn process_key_import(
+    store: &Path,
+    name: &str,
+    data: &[u8],
+    public: bool,
+    passphrase: impl FnOnce() -> Result<String>,
+) -> Result<KeyEntry> {
+    check_name(name)?;
+    if entry_path(store, name).exists() {
+        anyhow::bail!("Key {} already exists in the keystore", name);
+    }
+
+    let (verifying_key, private) = if public || is_public_key(data) {
+        (decode_verifying_key(data)?, None)
+    } else {
+        let key = decode_signing_key(&process_unprotect_key(data, passphrase)?)?;
+        (key.verifying_key(), Some(data))
+    };
+
+    let entry = KeyEntry {
+        name: name.to_string(),
+        versions: vec![KeyVersion::new(verifying_key.as_bytes())],
+        has_private: private.is_some(),
+    };
+    fs::create_dir_all(store)?;
+    if let Some(private) = private {
+        write_private_file(&private_path(store, name), private)?;
+    }
+    write_entry(store, &entry)?;
+
+    Ok(entry)
+}
+
+/// 生成新的私钥替换当前私钥，旧版本标记为 retired，公钥仍然保留
+pub fn process_key_rotate(
+    store: &Path,
+    name: &str,
+    protect: impl FnOnce(Vec<u8>) -> Result<Vec<u8>>,
+) -> Result<KeyEntry> {
+    let mut entry = read_entry(store, name)?;
+    if !entry.has_private {
+        anyhow::bail!("Key {} only has a public key and cannot be rotated", name);
+    }
+
+    let key = SigningKey::generate(&mut OsRng);
+    let private = protect(encode_signing_key(&key, KeyFormat::Raw)?)?;
+
+    let retired = now();
+    for version in entry.versions.iter_mut() {
+        version.retired.get_or_insert_with(|| retired.clone());
+    }
+    entry
+        .versions
+        .push(KeyVersion::new(key.verifying_key().as_bytes()));
+
+    write_private_file(&private_path(store, name), &private)?;
+    write_entry(store, &entry)?;
+
+    Ok(entry)
+}
+
+pub fn process_key_remove(store: &Path, name: &str) -> Result<()> {
+    let entry = read_entry(store, name)?;
+    if entry.has_private {
+        fs::remove_file(private_path(store, name))?;
+    }
+    fs::remove_file(entry_path(store, name))?;
+    Ok(())
+}
+
+/// 当前版本的私钥文件内容，可能被 passphrase 保护
+pub fn process_key_private(store: &Path, name: &str) -> Result<Vec<u8>> {
+    let entry = read_entry(store, name)?;
+    if !entry.has_private {
+        anyhow::bail!("Key {} only has a public key", name);
+    }
+    Ok(fs::read(private_path(store, name))?)
+}
+
+/// 按名称或者 key id（至少 4 位的前缀）查找，名称对应当前版本
+pub fn process_key_resolve(store: &Path, id: &str) -> Result<(KeyEntry, KeyVersion)> {
+    if check_name(id).is_ok() && entry_path(store, id).exists() {
+        let entry = read_entry(store, id)?;
+        let version = entry.current().clone();
+        return Ok((entry, version));
+    }
+
+    let id = id.to_lowercase();
+    if id.len() < MIN_KEY_ID_PREFIX || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("No key named {} in the keystore", id);
+    }
+    let mut found = Vec::new();
+    for entry in process_key_list(store)? {
+        for version in entry.versions.iter().filter(|v| v.key_id.starts_with(&id)) {
+            found.push((entry.clone(), version.clone()));
+        }
+    }
+    match found.len() {
+        0 => anyhow::bail!("No key with name or key id {} in the keystore", id),
+        1 => Ok(found.remove(0)),
+        _ => anyhow::bail!("Key id {} is ambiguous, use a longer prefix", id),
+    }
+}
+
+/// 签名使用的私钥，只能使用当前版本，返回私钥所属的名称
+pub fn process_key_signing_key(store: &Path, id: &str) -> Result<(String, Vec<u8>)> {
+    let (entry, version) = process_key_resolve(store, id)?;
+    if version != *entry.current() {
+        anyhow::bail!(
+            "Key {} of {} has been rotated, sign with the current key {}",
+            version.key_id,
+            entry.name,
+            entry.current().key_id
+        );
+    }
+    let private = process_key_private(store, &entry.name)?;
+    Ok((entry.name, private))
+}
+
+fn read_entry(store: &Path, name: &str) -> Result<KeyEntry> {
+    check_name(name)?;
+    let path = entry_path(store, name);
+    if !path.exists() {
+        anyhow::bail!("No key named {} in the keystore", name);
+    }
+    let mut entry: KeyEntry = serde_json::from_slice(&fs::read(&path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid keystore file {}: {}", path.display(), e))?;
+    if entry.versions.is_empty() {
+        anyhow::bail!("Invalid keystore file {}: no key versions", path.display());
+    }
+    entry.has_private = private_path(store, name).exists();
+    Ok(entry)
+}
+
+fn write_entry(store: &Path, entry: &KeyEntry) -> Result<()> {
+    let content = serde_json::to_string_pretty(entry)?;
+    fs::write(entry_path(store, &entry.name), content + "\n")?;
+    Ok(())
+}
+
+fn entry_path(store: &Path, name: &str) -> PathBuf {
+    store.join(format!("{}.json", name))
+}
+
+fn private_path(store: &Path, name: &str) -> PathBuf {
+    store.join(format!("{}.key", name))
+}
+
+// 名称会作为文件名，只允许字母、数字和 . _ -
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid key name {:?}: use letters, digits, '.', '_' and '-'",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_protect_key;
+
+    const SIGNING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.signing_key");
+    const VERIFY_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.verifying_key");
+
+    fn temp_store(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rcli-keystore-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn no_passphrase() -> Result<String> {
+        anyhow::bail!("no passphrase")
+    }
+
+    #[test]
+    fn test_keystore_import_rotate() -> Result<()> {
+        let store = temp_store("rotate");
+        let entry = process_key_import(&store, "release", SIGNING_KEY, false, no_passphrase)?;
+        let key_id = entry.current().key_id.clone();
+        assert_eq!(key_id, key_fingerprint(VERIFY_KEY));
+        assert!(process_key_import(&store, "release", SIGNING_KEY, false, no_passphrase).is_err());
+
+        let entry = process_key_rotate(&store, "release", Ok)?;
+        assert_eq!(entry.versions.len(), 2);
+        assert!(entry.versions[0].retired.is_some());
+        assert_ne!(entry.current().key_id, key_id);
+
+        // 旧的公钥仍然可以通过 key id 找到，但是不能再用来签名
+        let (_, version) = process_key_resolve(&store, &key_id[..6])?;
+        assert_eq!(version.public_key()?, VERIFY_KEY);
+        assert!(process_key_signing_key(&store, &key_id).is_err());
+        let (name, private) = process_key_signing_key(&store, "release")?;
+        assert_eq!(name, "release");
+        assert_eq!(
+            decode_signing_key(&private)?
+                .verifying_key()
+                .as_bytes()
+                .to_vec(),
+            entry.current().public_key()?
+        );
+
+        process_key_remove(&store, "release")?;
+        assert!(process_key_list(&store)?.is_empty());
+        fs::remove_dir_all(&store)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keystore_public_and_protected_keys() -> Result<()> {
+        let store = temp_store("public");
+        let entry = process_key_import(&store, "alice", VERIFY_KEY, true, no_passphrase)?;
+        assert!(!entry.has_private);
+        assert!(process_key_rotate(&store, "alice", Ok).is_err());
+        assert!(process_key_signing_key(&store, "alice").is_err());
+
+        let protected = process_protect_key(SIGNING_KEY, "secret")?;
+        let entry =
+            process_key_import(
+                &store,
+                "bob",
+                &protected,
+                false,
+                || Ok("secret".to_string()),
+            )?;
+        assert_eq!(entry.current().public_key()?, VERIFY_KEY);
+        assert_eq!(process_key_private(&store, "bob")?, protected);
+
+        // 两个 entry 的公钥相同，key id 有歧义
+        assert!(process_key_resolve(&store, &entry.current().key_id).is_err());
+        let names = process_key_list(&store)?
+            .into_iter()
+            .map(|e| e.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["alice", "bob"]);
+
+        assert!(process_key_import(&store, "../evil", VERIFY_KEY, true, no_passphrase).is_err());
+        fs::remove_dir_all(&store)?;
+        Ok(())
+    }
+}
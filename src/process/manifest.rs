@@ -0,0 +1,218 @@
+use anyhow::Result;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+// manifest 格式，每个文件一行，按路径排序，路径使用 / 分隔并且放在最后，可以包含空格：
+// # rcli manifest v1
+// <blake3 hex>  <size>  <relative path>
+const MANIFEST_HEADER: &str = "# rcli manifest v1";
+
+/// 目录中所有文件的路径、大小和 blake3 hash，签名的是 to_bytes 的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct Manifest {
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub size: u64,
+    pub hash: String,
+}
+
+/// 实际目录相对于 manifest 的差异
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub missing: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl Manifest {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut content = format!("{}\n", MANIFEST_HEADER);
+        for (path, entry) in &self.entries {
+            let _ = writeln!(content, "{}  {}  {}", entry.hash, entry.size, path);
+        }
+        content.into_bytes()
+    }
+
+    pub fn parse(content: &[u8]) -> Result<Self> {
+        let content = std::str::from_utf8(content)?;
+        let mut lines = content.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            anyhow::bail!("Invalid manifest: missing '{}' header", MANIFEST_HEADER);
+        }
+
+        let mut entries = BTreeMap::new();
+        for (i, line) in lines.enumerate() {
+            let invalid = || anyhow::anyhow!("Invalid manifest line {}: {}", i + 2, line);
+            let mut parts = line.splitn(3, "  ");
+            let (hash, size, path) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(hash), Some(size), Some(path)) => (hash, size, path),
+                _ => return Err(invalid()),
+            };
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            let entry = ManifestEntry {
+                size: size.parse().map_err(|_| invalid())?,
+                hash: hash.to_string(),
+            };
+            if entries.insert(path.to_string(), entry).is_some() {
+                anyhow::bail!("Invalid manifest: duplicate path {}", path);
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// self 是签名的 manifest，actual 是目录现在的状态
+    pub fn diff(&self, actual: &Manifest) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        for (path, entry) in &self.entries {
+            match actual.entries.get(path) {
+                Some(current) if current == entry => {}
+                Some(_) => diff.modified.push(path.clone()),
+                None => diff.missing.push(path.clone()),
+            }
+        }
+        diff.added = actual
+            .entries
+            .keys()
+            .filter(|path| !self.entries.contains_key(*path))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl ManifestDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.missing.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// 递归遍历目录，计算每个普通文件的 blake3 hash，不跟随符号链接
+/// exclude 是写在目录中的 manifest 和签名文件，它们不能出现在自己的 manifest 里
+pub fn process_manifest_build(dir: &Path, exclude: &[&Path]) -> Result<Manifest> {
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+
+    // 从规范化的根目录开始遍历，遍历到的路径可以直接和 exclude 比较
+    let root = dir.canonicalize()?;
+    let exclude = exclude
+        .iter()
+        .filter_map(|path| normalize(path))
+        .collect::<Vec<_>>();
+    let mut manifest = Manifest::default();
+    walk(&root, "", &exclude, &mut manifest)?;
+    Ok(manifest)
+}
+
+// 输出文件可能还不存在，只规范化它所在的目录
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+fn walk(dir: &Path, prefix: &str, exclude: &[PathBuf], manifest: &mut Manifest) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("File name is not UTF-8: {:?}", entry.path()))?;
+        if name.contains('\n') {
+            anyhow::bail!("File name contains a newline: {:?}", entry.path());
+        }
+        let path = format!("{}{}", prefix, name);
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), &format!("{}/", path), exclude, manifest)?;
+        } else if exclude.contains(&entry.path()) {
+            continue;
+        } else if file_type.is_file() {
+            let hash = blake3::Hasher::new()
+                .update_mmap_rayon(entry.path())?
+                .finalize();
+            let size = entry.metadata()?.len();
+            manifest.entries.insert(
+                path,
+                ManifestEntry {
+                    size,
+                    hash: hash.to_hex().to_string(),
+                },
+            );
+        } else {
+            // 符号链接可能指向目录之外，签名无法覆盖它指向的内容
+            anyhow::bail!(
+                "Unsupported file type (symlink?): {}",
+                entry.path().display()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_diff() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("rcli-manifest-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub dir"))?;
+        fs::write(dir.join("a.txt"), "hello")?;
+        fs::write(dir.join("sub dir/b.txt"), "world")?;
+
+        let manifest = process_manifest_build(&dir, &[])?;
+        assert_eq!(manifest.len(), 2);
+        let content = manifest.to_bytes();
+        assert!(String::from_utf8(content.clone())?.contains("  5  sub dir/b.txt\n"));
+        assert_eq!(Manifest::parse(&content)?, manifest);
+
+        fs::write(dir.join("a.txt"), "hellO")?;
+        fs::remove_file(dir.join("sub dir/b.txt"))?;
+        fs::write(dir.join("c.txt"), "new")?;
+        let diff = manifest.diff(&process_manifest_build(&dir, &[])?);
+        assert_eq!(
+            diff,
+            ManifestDiff {
+                added: vec!["c.txt".to_string()],
+                missing: vec!["sub dir/b.txt".to_string()],
+                modified: vec!["a.txt".to_string()],
+            }
+        );
+
+        assert!(Manifest::parse(b"a.txt").is_err());
+
+        // 写在目录中的 manifest 和签名文件不会被当作新增的文件
+        let manifest_path = dir.join("dir.manifest");
+        let sig_path = dir.join("sub dir/dir.sig");
+        fs::write(&manifest_path, "manifest")?;
+        fs::write(&sig_path, "sig")?;
+        let manifest = process_manifest_build(&dir, &[&manifest_path, &sig_path])?;
+        assert_eq!(manifest.len(), 2);
+        assert!(!String::from_utf8(manifest.to_bytes())?.contains("dir."));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{decode_signing_key, decode_verifying_key, key_fingerprint, TextSigner, TextVerifier};
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::{
+    io::{self, Read},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// minisign 格式：https://jedisct1.github.io/minisign/
+// 公钥：base64("Ed" || key_id(8) || public_key(32))
+// 签名：base64("ED" || key_id(8) || signature(64))，ED 是对 BLAKE2b-512 的结果签名，Ed 是旧版本的直接签名
+// 全局签名：对 signature || trusted comment 签名，保证 trusted comment 不能被修改
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+const ALG_PUBLIC: &[u8; 2] = b"Ed";
+const ALG_HASHED: &[u8; 2] = b"ED";
+const KEY_ID_LEN: usize = 8;
+
+/// minisign 公钥，key id 需要和签名中的一致
+pub struct MinisignPublicKey {
+    pub key_id: [u8; KEY_ID_LEN],
+    pub key: VerifyingKey,
+}
+
+pub struct MinisignSigner {
+    key: SigningKey,
+    trusted_comment: String,
+}
+
+pub struct MinisignVerifier {
+    public: MinisignPublicKey,
+}
+
+struct MinisignSignature {
+    algorithm: [u8; 2],
+    key_id: [u8; KEY_ID_LEN],
+    signature: Signature,
+    trusted_comment: String,
+    global_signature: Signature,
+}
+
+impl MinisignSigner {
+    pub fn try_new(key: impl AsRef<[u8]>, trusted_comment: Option<&str>) -> Result<Self> {
+        let trusted_comment = match trusted_comment {
+            Some(comment) if comment.contains(['\r', '\n']) => {
+                anyhow::bail!("Trusted comment must be a single line")
+            }
+            Some(comment) => comment.to_string(),
+            None => minisign_trusted_comment(None),
+        };
+
+        Ok(Self {
+            key: decode_signing_key(key.as_ref())?,
+            trusted_comment,
+        })
+    }
+}
+
+impl MinisignVerifier {
+    /// 支持 minisign 公钥文件，也支持 rcli 的 ed25519 公钥，后者的 key id 由公钥派生
+    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
+        let key = key.as_ref();
+        let public = if is_minisign_public_key(key) {
+            decode_minisign_public_key(key)?
+        } else {
+            let key = decode_verifying_key(key)?;
+            MinisignPublicKey {
+                key_id: minisign_key_id(&key),
+                key,
+            }
+        };
+
+        Ok(Self { public })
+    }
+}
+
+impl TextSigner for MinisignSigner {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        let mut hasher = Blake2b512::new();
+        io::copy(reader, &mut hasher)?;
+        let signature = self.key.sign(&hasher.finalize());
+
+        let mut global = signature.to_bytes().to_vec();
+        global.extend_from_slice(self.trusted_comment.as_bytes());
+        let global_signature = self.key.sign(&global);
+
+        let mut sig = ALG_HASHED.to_vec();
+        sig.extend_from_slice(&minisign_key_id(&self.key.verifying_key()));
+        sig.extend_from_slice(&signature.to_bytes());
+
+        let content = format!(
+            "{}signature from rcli secret key\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            STANDARD.encode(sig),
+            TRUSTED_PREFIX,
+            self.trusted_comment,
+            STANDARD.encode(global_signature.to_bytes())
+        );
+        Ok(content.into_bytes())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.verifying_key().as_bytes())
+    }
+}
+
+impl TextVerifier for MinisignVerifier {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        let sig = MinisignSignature::parse(sig)?;
+        if sig.key_id != self.public.key_id {
+            anyhow::bail!(
+                "Signature was made with minisign key {}, but the given key is {}",
+                display_key_id(&sig.key_id),
+                display_key_id(&self.public.key_id)
+            );
+        }
+
+        let message = if &sig.algorithm == ALG_HASHED {
+            let mut hasher = Blake2b512::new();
+            io::copy(reader, &mut hasher)?;
+            hasher.finalize().to_vec()
+        } else {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            buf
+        };
+        if self.public.key.verify(&message, &sig.signature).is_err() {
+            return Ok(false);
+        }
+
+        let mut global = sig.signature.to_bytes().to_vec();
+        global.extend_from_slice(sig.trusted_comment.as_bytes());
+        Ok(self
+            .public
+            .key
+            .verify(&global, &sig.global_signature)
+            .is_ok())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.public.key.as_bytes())
+    }
+}
+
+impl MinisignSignature {
+    fn parse(content: &[u8]) -> Result<Self> {
+        let content = std::str::from_utf8(content)?;
+        let mut lines = content.lines();
+        let invalid = || anyhow::anyhow!("Invalid minisign signature");
+
+        lines
+            .next()
+            .filter(|l| l.starts_with(UNTRUSTED_PREFIX))
+            .ok_or_else(invalid)?;
+        let sig = STANDARD.decode(lines.next().ok_or_else(invalid)?.trim())?;
+        let trusted_comment = lines
+            .next()
+            .and_then(|l| l.strip_prefix(TRUSTED_PREFIX))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Invalid minisign signature: missing trusted comment")
+            })?;
+        let global = STANDARD.decode(lines.next().ok_or_else(invalid)?.trim())?;
+
+        if sig.len() != 2 + KEY_ID_LEN + 64 || global.len() != 64 {
+            return Err(invalid());
+        }
+        let algorithm: [u8; 2] = sig[..2].try_into()?;
+        if &algorithm != ALG_HASHED && &algorithm != ALG_PUBLIC {
+            anyhow::bail!("Unsupported minisign signature algorithm");
+        }
+
+        Ok(Self {
+            algorithm,
+            key_id: sig[2..2 + KEY_ID_LEN].try_into()?,
+            signature: Signature::from_bytes(sig[2 + KEY_ID_LEN..].try_into()?),
+            trusted_comment: trusted_comment.to_string(),
+            global_signature: Signature::from_bytes(global.as_slice().try_into()?),
+        })
+    }
+}
+
+/// trusted comment 在签名范围内，不能包含换行
+pub fn process_minisign_sign(
+    reader: &mut dyn Read,
+    key: &[u8],
+    trusted_comment: &str,
+) -> Result<Vec<u8>> {
+    MinisignSigner::try_new(key, Some(trusted_comment))?.sign(reader)
+}
+
+/// rcli 的 minisign key id 是公钥的 key id 的前 8 个字节
+pub fn minisign_key_id(key: &VerifyingKey) -> [u8; KEY_ID_LEN] {
+    let mut id = [0u8; KEY_ID_LEN];
+    id.copy_from_slice(&blake3::hash(key.as_bytes()).as_bytes()[..KEY_ID_LEN]);
+    id
+}
+
+/// 和 minisign 一样的默认 trusted comment
+pub fn minisign_trusted_comment(file: Option<&str>) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    match file {
+        Some(file) => format!("timestamp:{}\tfile:{}\thashed", timestamp, file),
+        None => format!("timestamp:{}\thashed", timestamp),
+    }
+}
+
+/// 签名文件中的 trusted comment，只有验证通过后才是可信的
+pub fn minisign_signature_comment(content: &[u8]) -> Result<String> {
+    Ok(MinisignSignature::parse(content)?.trusted_comment)
+}
+
+pub fn is_minisign_signature(content: &str) -> bool {
+    content.starts_with(UNTRUSTED_PREFIX) && content.contains(TRUSTED_PREFIX)
+}
+
+/// 带 untrusted comment 的公钥文件，或者只有 base64 的一行
+pub fn is_minisign_public_key(data: &[u8]) -> bool {
+    let text = std::str::from_utf8(data).map(str::trim).unwrap_or_default();
+    if text.starts_with(UNTRUSTED_PREFIX) {
+        return true;
+    }
+    !text.contains('\n')
+        && STANDARD
+            .decode(text)
+            .is_ok_and(|key| key.len() == 2 + KEY_ID_LEN + 32 && key.starts_with(ALG_PUBLIC))
+}
+
+pub fn decode_minisign_public_key(data: &[u8]) -> Result<MinisignPublicKey> {
+    let text = std::str::from_utf8(data)?;
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with(UNTRUSTED_PREFIX))
+        .ok_or_else(|| anyhow::anyhow!("Invalid minisign public key: missing key"))?;
+    let key = STANDARD
+        .decode(line)
+        .map_err(|e| anyhow::anyhow!("Invalid minisign public key: {}", e))?;
+    if key.len() != 2 + KEY_ID_LEN + 32 || !key.starts_with(ALG_PUBLIC) {
+        anyhow::bail!("Invalid minisign public key: not an ed25519 key");
+    }
+
+    Ok(MinisignPublicKey {
+        key_id: key[2..2 + KEY_ID_LEN].try_into()?,
+        key: VerifyingKey::from_bytes(key[2 + KEY_ID_LEN..].try_into()?)?,
+    })
+}
+
+pub fn encode_minisign_public_key(key: &VerifyingKey) -> Vec<u8> {
+    let key_id = minisign_key_id(key);
+    let mut data = ALG_PUBLIC.to_vec();
+    data.extend_from_slice(&key_id);
+    data.extend_from_slice(key.as_bytes());
+
+    format!(
+        "{}minisign public key {}\n{}\n",
+        UNTRUSTED_PREFIX,
+        display_key_id(&key_id),
+        STANDARD.encode(data)
+    )
+    .into_bytes()
+}
+
+// minisign 把 key id 当作小端的 u64 显示
+fn display_key_id(key_id: &[u8; KEY_ID_LEN]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &[u8] = include_bytes!("../../fixtures/minisign.pub");
+    const MESSAGE: &[u8] = include_bytes!("../../fixtures/minisign_test.txt");
+    const SIGNATURE: &[u8] = include_bytes!("../../fixtures/minisign_test.txt.minisig");
+    const LEGACY_SIGNATURE: &[u8] =
+        include_bytes!("../../fixtures/minisign_test.txt.legacy.minisig");
+    const SIGNING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.signing_key");
+
+    #[test]
+    fn test_verify_minisign_fixtures() -> Result<()> {
+        // 由 minisign 生成的签名，包括 prehashed 和旧版本的格式
+        let verifier = MinisignVerifier::try_new(PUBLIC_KEY)?;
+        assert_eq!(display_key_id(&verifier.public.key_id), "E7620F1842B4E81F");
+        assert!(verifier.verify(&mut &MESSAGE[..], SIGNATURE)?);
+        assert!(verifier.verify(&mut &MESSAGE[..], LEGACY_SIGNATURE)?);
+        assert!(!verifier.verify(&mut &b"Test"[..], SIGNATURE)?);
+        assert_eq!(
+            minisign_signature_comment(SIGNATURE)?,
+            "timestamp:1556193335\tfile:test"
+        );
+
+        // 修改 trusted comment 会导致全局签名验证失败
+        let tampered = String::from_utf8(SIGNATURE.to_vec())?.replace("file:test", "file:evil");
+        assert!(!verifier.verify(&mut &MESSAGE[..], tampered.as_bytes())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_sign_verify() -> Result<()> {
+        let signer = MinisignSigner::try_new(SIGNING_KEY, Some("release v1"))?;
+        let sig = signer.sign(&mut &MESSAGE[..])?;
+        assert!(is_minisign_signature(std::str::from_utf8(&sig)?));
+        assert_eq!(minisign_signature_comment(&sig)?, "release v1");
+
+        let public = encode_minisign_public_key(&decode_signing_key(SIGNING_KEY)?.verifying_key());
+        assert!(is_minisign_public_key(&public));
+        let verifier = MinisignVerifier::try_new(&public)?;
+        assert!(verifier.verify(&mut &MESSAGE[..], &sig)?);
+        assert_eq!(verifier.key_id(), signer.key_id());
+
+        // key id 不一致时报错，而不是简单地返回验证失败
+        let other = MinisignVerifier::try_new(PUBLIC_KEY)?;
+        assert!(other.verify(&mut &MESSAGE[..], &sig).is_err());
+        assert!(MinisignSigner::try_new(SIGNING_KEY, Some("a\nb")).is_err());
+        Ok(())
+    }
+}
@@ -1,12 +1,50 @@
+mod age_encrypt;
 mod b64;
 mod csv_convert;
+mod data_uri;
 mod gen_pass;
+mod hash;
+mod hex_dump;
 mod http_serve;
+mod id;
+mod jwt;
+mod key_format;
+mod key_protect;
+mod keystore;
+mod manifest;
+mod minisign;
+mod otp;
+mod pwned;
+mod qp;
+mod sig_envelope;
+mod sshsig;
+mod text_encrypt;
 mod text_sign_verify;
+mod token;
+mod url_codec;
 
 // 需要在这里声明
+pub use age_encrypt::*;
 pub use b64::*;
 pub use csv_convert::*;
+pub use data_uri::*;
 pub use gen_pass::*;
+pub use hash::*;
+pub use hex_dump::*;
 pub use http_serve::*;
+pub use id::*;
+pub use jwt::*;
+pub use key_format::*;
+pub use key_protect::*;
+pub use keystore::*;
+pub use manifest::*;
+pub use minisign::*;
+pub use otp::*;
+pub use pwned::*;
+pub use qp::*;
+pub use sig_envelope::*;
+pub use sshsig::*;
+pub use text_encrypt::*;
 pub use text_sign_verify::*;
+pub use token::*;
+pub use url_codec::*;
@@ -0,0 +1,245 @@
+use crate::{OtpAlgorithm, OtpKind};
+use anyhow::Result;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// RFC 4226 HOTP / RFC 6238 TOTP 的生成器
+/// TOTP 就是把当前时间按 period 切分后作为 HOTP 的 counter
+pub struct Otp {
+    secret: Vec<u8>,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+}
+
+impl Otp {
+    pub fn try_new(secret: &str, algorithm: OtpAlgorithm, digits: u32) -> Result<Self> {
+        Self::new(decode_secret(secret)?, algorithm, digits)
+    }
+
+    // digits 超过 9 时 10^digits 会溢出 u32
+    pub fn new(secret: Vec<u8>, algorithm: OtpAlgorithm, digits: u32) -> Result<Self> {
+        if !(6..=8).contains(&digits) {
+            anyhow::bail!("OTP digits must be between 6 and 8, got {}", digits);
+        }
+
+        Ok(Self {
+            secret,
+            algorithm,
+            digits,
+        })
+    }
+
+    pub fn hotp(&self, counter: u64) -> Result<String> {
+        let hash = self.hmac(&counter.to_be_bytes())?;
+
+        // dynamic truncation: 取最后一个字节的低 4 位作为偏移量，截取 4 个字节
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes(hash[offset..offset + 4].try_into()?) & 0x7fff_ffff;
+        let code = binary % 10u32.pow(self.digits);
+
+        Ok(format!("{:0width$}", code, width = self.digits as usize))
+    }
+
+    pub fn totp(&self, time: u64, period: u64) -> Result<String> {
+        anyhow::ensure!(period > 0, "OTP period must be greater than 0");
+        self.hotp(time / period)
+    }
+
+    /// 在 [counter, counter + window] 范围内查找匹配的 counter
+    pub fn verify_hotp(&self, code: &str, counter: u64, window: u64) -> Result<Option<u64>> {
+        for c in counter..=counter.saturating_add(window) {
+            if constant_time_eq(self.hotp(c)?.as_bytes(), code.as_bytes()) {
+                return Ok(Some(c));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 允许前后 skew 个时间步长的误差，返回匹配的时间步长偏移
+    pub fn verify_totp(
+        &self,
+        code: &str,
+        time: u64,
+        period: u64,
+        skew: u64,
+    ) -> Result<Option<i64>> {
+        anyhow::ensure!(period > 0, "OTP period must be greater than 0");
+        let current = time / period;
+        let start = current.saturating_sub(skew);
+
+        for step in start..=current.saturating_add(skew) {
+            if constant_time_eq(self.hotp(step)?.as_bytes(), code.as_bytes()) {
+                return Ok(Some(step as i64 - current as i64));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn hmac(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        // hmac 接受任意长度的 key，new_from_slice 不会失败
+        let hash = match self.algorithm {
+            OtpAlgorithm::Sha1 => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(&self.secret)?;
+                mac.update(msg);
+                mac.finalize().into_bytes().to_vec()
+            }
+            OtpAlgorithm::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)?;
+                mac.update(msg);
+                mac.finalize().into_bytes().to_vec()
+            }
+            OtpAlgorithm::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_from_slice(&self.secret)?;
+                mac.update(msg);
+                mac.finalize().into_bytes().to_vec()
+            }
+        };
+
+        Ok(hash)
+    }
+}
+
+/// 生成一个新的随机 secret，返回 base32 编码的字符串
+pub fn process_otp_secret(length: usize) -> String {
+    let mut secret = vec![0u8; length];
+    OsRng.fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+/// 生成 authenticator app 可以导入的 otpauth:// URI
+pub fn process_otpauth_uri(
+    kind: OtpKind,
+    secret: &str,
+    issuer: &str,
+    account: &str,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+    period_or_counter: u64,
+) -> String {
+    let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC).to_string();
+    let account = utf8_percent_encode(account, NON_ALPHANUMERIC).to_string();
+    let moving_factor = match kind {
+        OtpKind::Totp => format!("period={}", period_or_counter),
+        OtpKind::Hotp => format!("counter={}", period_or_counter),
+    };
+
+    format!(
+        "otpauth://{}/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&{}",
+        kind,
+        issuer,
+        account,
+        secret,
+        issuer,
+        algorithm.to_string().to_uppercase(),
+        digits,
+        moving_factor
+    )
+}
+
+pub fn unix_time() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+// authenticator 里展示的 secret 通常是小写、带空格、带 padding 的
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let normalized = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .collect::<String>()
+        .to_uppercase();
+
+    BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid base32 secret: {}", e))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4226 Appendix D
+    #[test]
+    fn test_hotp_rfc4226_vectors() -> Result<()> {
+        let otp = Otp::new(b"12345678901234567890".to_vec(), OtpAlgorithm::Sha1, 6)?;
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(otp.hotp(counter as u64)?, *code);
+        }
+
+        assert_eq!(otp.verify_hotp("969429", 1, 2)?, Some(3));
+        assert_eq!(otp.verify_hotp("969429", 0, 2)?, None);
+        Ok(())
+    }
+
+    // RFC 6238 Appendix B
+    #[test]
+    fn test_totp_rfc6238_vectors() -> Result<()> {
+        let sha1 = Otp::new(b"12345678901234567890".to_vec(), OtpAlgorithm::Sha1, 8)?;
+        let sha256 = Otp::new(
+            b"12345678901234567890123456789012".to_vec(),
+            OtpAlgorithm::Sha256,
+            8,
+        )?;
+        let sha512 = Otp::new(
+            b"1234567890123456789012345678901234567890123456789012345678901234".to_vec(),
+            OtpAlgorithm::Sha512,
+            8,
+        )?;
+
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+
+        for (time, c1, c256, c512) in vectors {
+            assert_eq!(sha1.totp(time, 30)?, c1);
+            assert_eq!(sha256.totp(time, 30)?, c256);
+            assert_eq!(sha512.totp(time, 30)?, c512);
+        }
+
+        assert_eq!(sha1.verify_totp("07081804", 1111111139, 30, 1)?, Some(-1));
+        assert_eq!(sha1.verify_totp("07081804", 1111111169, 30, 1)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_secret_roundtrip() -> Result<()> {
+        let secret = process_otp_secret(20);
+        assert_eq!(secret.len(), 32);
+
+        // 小写、带空格和 padding 的 secret 也可以解析
+        let otp = Otp::try_new(
+            "gezd gnbv gy3t qojq gezd gnbv gy3t qojq====",
+            OtpAlgorithm::Sha1,
+            6,
+        )?;
+        assert_eq!(otp.hotp(0)?, "755224");
+
+        for digits in [0, 5, 9, 12] {
+            assert!(
+                Otp::new(b"12345678901234567890".to_vec(), OtpAlgorithm::Sha1, digits).is_err()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+// 生成的密码命中泄露库时，最多重新生成的次数
+const MAX_ATTEMPTS: usize = 100;
+
+/// Have I Been Pwned 的 SHA-1 ordered-by-hash 文件，每行格式为 `HASH:COUNT`
+/// 文件按 hash 排好序，所以可以直接在文件上做二分查找，不需要全部读入内存
+pub struct BreachDb {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl BreachDb {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// 返回密码在泄露库中出现的次数，没有出现则返回 0
+    pub fn count(&mut self, password: &str) -> Result<u64> {
+        let hash = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>();
+
+        self.lookup(&hash)
+    }
+
+    /// 反复调用 generate，直到生成一个不在泄露库中的密码
+    pub fn reject_breached(
+        &mut self,
+        mut generate: impl FnMut() -> Result<String>,
+    ) -> Result<String> {
+        for _ in 0..MAX_ATTEMPTS {
+            let password = generate()?;
+            if self.count(&password)? == 0 {
+                return Ok(password);
+            }
+        }
+
+        anyhow::bail!(
+            "Could not generate a password outside the breach database after {} attempts",
+            MAX_ATTEMPTS
+        )
+    }
+
+    // 按字节偏移二分，每次取 mid 之后的第一整行来比较
+    // 不变量：如果 hash 存在，它所在行的起始位置一定在 [lo, hi) 之间
+    fn lookup(&mut self, hash: &str) -> Result<u64> {
+        let (mut lo, mut hi) = (0, self.len);
+        let mut line = String::new();
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let start = self.line_start(mid)?;
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+
+            line.clear();
+            let n = self.reader.read_line(&mut line)?;
+            let (line_hash, count) = line.trim_end().split_once(':').ok_or_else(|| {
+                anyhow::anyhow!("Malformed breach database line at offset {}", start)
+            })?;
+
+            match line_hash.to_ascii_uppercase().as_str().cmp(hash) {
+                Ordering::Less => lo = start + n as u64,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal => return Ok(count.trim().parse()?),
+            }
+        }
+
+        Ok(0)
+    }
+
+    // 定位到 pos 之后（含 pos）第一行的起始位置
+    fn line_start(&mut self, pos: u64) -> Result<u64> {
+        if pos == 0 {
+            self.reader.seek(SeekFrom::Start(0))?;
+            return Ok(0);
+        }
+
+        self.reader.seek(SeekFrom::Start(pos - 1))?;
+        let mut skipped = Vec::new();
+        let n = self.reader.read_until(b'\n', &mut skipped)?;
+
+        Ok(pos - 1 + n as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+    // sha1("hello")    = AAF4C61DDCC5E8A2DABEDE0F3B482CD9AEA9434D
+    const DB: &str = "\
+000000005AD76BD555C1D6D771DE417A4B87E4B4:10\r
+5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r
+7C4A8D09CA3762AF61E59520943DC26494F8941B:37359195\r
+AAF4C61DDCC5E8A2DABEDE0F3B482CD9AEA9434D:312\r
+FFFFFFF8A0382AA9C8D9536EFBA77F261815334D:1\r
+";
+
+    #[test]
+    fn test_breach_db_count() -> Result<()> {
+        let path = std::env::temp_dir().join("rcli_pwned_test.txt");
+        std::fs::write(&path, DB)?;
+        let mut db = BreachDb::open(&path)?;
+
+        assert_eq!(db.count("password")?, 9659365);
+        assert_eq!(db.count("hello")?, 312);
+        assert_eq!(db.count("not-in-the-list")?, 0);
+        assert_eq!(db.lookup("000000005AD76BD555C1D6D771DE417A4B87E4B4")?, 10);
+        assert_eq!(db.lookup("FFFFFFF8A0382AA9C8D9536EFBA77F261815334D")?, 1);
+
+        let password = db.reject_breached(|| Ok("hello world".to_string()))?;
+        assert_eq!(password, "hello world");
+        assert!(db.reject_breached(|| Ok("hello".to_string())).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use quoted_printable::ParseMode;
+use std::io::{Read, Write};
+
+/// RFC 2045 quoted-printable，每行不超过 76 个字符
+/// binary 为 true 时换行也会被编码，否则换行统一输出为 CRLF
+pub fn process_qp_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    binary: bool,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    let encoded = if binary {
+        quoted_printable::encode_binary(&buf)
+    } else {
+        // 文本模式下只有 CRLF 会被当作换行，先把单独的 LF 转成 CRLF
+        let mut text = Vec::with_capacity(buf.len());
+        for (i, b) in buf.iter().enumerate() {
+            if *b == b'\n' && (i == 0 || buf[i - 1] != b'\r') {
+                text.push(b'\r');
+            }
+            text.push(*b);
+        }
+        quoted_printable::encode(&text)
+    };
+
+    writer.write_all(&encoded)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// strict 为 false 时尽量容忍不规范的输入
+pub fn process_qp_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    strict: bool,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    let mode = if strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Robust
+    };
+    let decoded = quoted_printable::decode(&buf, mode)
+        .map_err(|e| anyhow::anyhow!("Invalid quoted-printable input: {}", e))?;
+
+    writer.write_all(&decoded)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qp_encode_decode() -> Result<()> {
+        let input = format!("héllo=world\n{}\n", "x".repeat(100));
+
+        let mut encoded = Vec::new();
+        process_qp_encode(&mut input.as_bytes(), &mut encoded, false)?;
+        let text = String::from_utf8(encoded.clone())?;
+        assert!(text.starts_with("h=C3=A9llo=3Dworld\r\n"));
+        assert!(text.lines().all(|line| line.len() <= 76));
+
+        let mut decoded = Vec::new();
+        process_qp_decode(&mut encoded.as_slice(), &mut decoded, true)?;
+        assert_eq!(String::from_utf8(decoded)?, input.replace('\n', "\r\n"));
+
+        assert!(process_qp_decode(&mut "=ZZ".as_bytes(), &mut Vec::new(), true).is_err());
+        Ok(())
+    }
+}
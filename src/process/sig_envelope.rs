@@ -0,0 +1,179 @@
+use crate::{
+    is_minisign_signature, is_sshsig_signature, EnvelopeFormat, SignatureEncoding, TextSignFormat,
+};
+use anyhow::Result;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::SystemTime};
+
+/// detached 签名文件，comment 和 timestamp 只是元数据，不在签名范围内
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignatureEnvelope {
+    pub algorithm: String,
+    pub key_id: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub signature: String,
+}
+
+/// --sig 的内容，可能是签名文件，也可能是裸的 base64 签名
+#[derive(Debug)]
+pub enum ParsedSignature {
+    Envelope(SignatureEnvelope),
+    Raw(Vec<u8>),
+    // 自带格式的签名文件，例如 minisign 和 SSH 签名，内容原样交给 verifier
+    Native(TextSignFormat, Vec<u8>),
+}
+
+impl SignatureEnvelope {
+    pub fn new(
+        format: TextSignFormat,
+        key_id: String,
+        sig: &[u8],
+        comment: Option<String>,
+    ) -> Self {
+        Self {
+            algorithm: format.to_string(),
+            key_id,
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            comment,
+            signature: URL_SAFE_NO_PAD.encode(sig),
+        }
+    }
+
+    pub fn format(&self) -> Result<TextSignFormat> {
+        self.algorithm.parse()
+    }
+
+    pub fn signature(&self) -> Result<Vec<u8>> {
+        Ok(URL_SAFE_NO_PAD.decode(&self.signature)?)
+    }
+
+    pub fn serialize(&self, format: EnvelopeFormat) -> Result<String> {
+        let content = match format {
+            EnvelopeFormat::Json => serde_json::to_string_pretty(self)?,
+            EnvelopeFormat::Yaml => serde_yaml::to_string(self)?,
+        };
+        Ok(content)
+    }
+}
+
+/// sig 是已存在的文件时读取文件内容，否则当作签名字符串本身
+/// 内容是 json 或 yaml 的签名文件时解析为 envelope，minisign 和 SSH 签名文件原样返回，否则按 encoding 解码
+pub fn process_parse_signature(sig: &str, encoding: SignatureEncoding) -> Result<ParsedSignature> {
+    let content = if Path::new(sig).is_file() {
+        std::fs::read_to_string(sig)?
+    } else {
+        sig.to_string()
+    };
+    let content = content.trim();
+
+    if is_minisign_signature(content) {
+        let content = format!("{}\n", content).into_bytes();
+        return Ok(ParsedSignature::Native(TextSignFormat::Minisign, content));
+    }
+    if is_sshsig_signature(content) {
+        let content = format!("{}\n", content).into_bytes();
+        return Ok(ParsedSignature::Native(TextSignFormat::Sshsig, content));
+    }
+
+    // yaml 是 json 的超集，一次解析就够了
+    if let Ok(envelope) = serde_yaml::from_str::<SignatureEnvelope>(content) {
+        return Ok(ParsedSignature::Envelope(envelope));
+    }
+
+    let sig = decode_signature(content, encoding).map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid signature: neither a signature file nor {} ({})",
+            encoding,
+            e
+        )
+    })?;
+    Ok(ParsedSignature::Raw(sig))
+}
+
+/// 裸签名的输出格式，webhook 的 header 通常使用 hex 或者标准 base64
+pub fn encode_signature(sig: &[u8], encoding: SignatureEncoding) -> String {
+    match encoding {
+        SignatureEncoding::Base64Url => URL_SAFE_NO_PAD.encode(sig),
+        SignatureEncoding::Base64 => STANDARD.encode(sig),
+        SignatureEncoding::Hex => HEXLOWER.encode(sig),
+    }
+}
+
+// 两种 base64 的字母表不同，解码时都接受
+fn decode_signature(content: &str, encoding: SignatureEncoding) -> Result<Vec<u8>> {
+    let sig = match encoding {
+        SignatureEncoding::Base64Url | SignatureEncoding::Base64 => URL_SAFE_NO_PAD
+            .decode(content)
+            .or_else(|_| STANDARD.decode(content))?,
+        SignatureEncoding::Hex => HEXLOWER_PERMISSIVE.decode(content.as_bytes())?,
+    };
+    Ok(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_roundtrip() -> Result<()> {
+        let envelope = SignatureEnvelope::new(
+            TextSignFormat::Ed25519,
+            "0123456789abcdef".to_string(),
+            b"signature",
+            Some("release v1.0".to_string()),
+        );
+
+        for format in [EnvelopeFormat::Json, EnvelopeFormat::Yaml] {
+            let content = envelope.serialize(format)?;
+            match process_parse_signature(&content, SignatureEncoding::Base64Url)? {
+                ParsedSignature::Envelope(parsed) => assert_eq!(parsed, envelope),
+                _ => panic!("expected an envelope"),
+            }
+        }
+        assert!(matches!(envelope.format()?, TextSignFormat::Ed25519));
+        assert_eq!(envelope.signature()?, b"signature");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_raw_signature() -> Result<()> {
+        let encoding = SignatureEncoding::Base64Url;
+        let sig = process_parse_signature("fixtures/ed25519.sig", encoding)?;
+        assert!(matches!(sig, ParsedSignature::Raw(sig) if sig.len() == 64));
+
+        let sig = process_parse_signature("7D1ujXdqgaMOVayfCqqvQAqgpRTUDZPTA_XzvrICtyM", encoding)?;
+        assert!(matches!(sig, ParsedSignature::Raw(sig) if sig.len() == 32));
+
+        assert!(process_parse_signature("not a signature!", encoding).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_signature_encoding() -> Result<()> {
+        let sig = [0xfbu8, 0xff, 0x00, 0x10];
+        for encoding in [
+            SignatureEncoding::Base64Url,
+            SignatureEncoding::Base64,
+            SignatureEncoding::Hex,
+        ] {
+            let content = encode_signature(&sig, encoding);
+            match process_parse_signature(&content, encoding)? {
+                ParsedSignature::Raw(parsed) => assert_eq!(parsed, sig),
+                _ => panic!("expected a raw signature"),
+            }
+        }
+        assert_eq!(
+            encode_signature(&sig, SignatureEncoding::Base64),
+            "+/8AEA=="
+        );
+        assert_eq!(encode_signature(&sig, SignatureEncoding::Hex), "fbff0010");
+        assert!(process_parse_signature("FBFF0010", SignatureEncoding::Hex).is_ok());
+        assert!(process_parse_signature("xyz", SignatureEncoding::Hex).is_err());
+        Ok(())
+    }
+}
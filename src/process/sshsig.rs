@@ -0,0 +1,477 @@
+use crate::{decode_signing_key, decode_verifying_key, key_fingerprint, TextSigner, TextVerifier};
+use anyhow::Result;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256, Sha512};
+use ssh_key::{
+    public::{Ed25519PublicKey, KeyData},
+    Algorithm, HashAlg, LineEnding, PublicKey, SshSig,
+};
+use std::{
+    io::{self, Read},
+    time::SystemTime,
+};
+
+// OpenSSH 的签名格式：https://github.com/openssh/openssh-portable/blob/master/PROTOCOL.sshsig
+// 签名的内容是 "SSHSIG" || namespace || reserved || hash 算法 || H(message)，每个字段前面是 4 字节的长度
+const MAGIC_PREAMBLE: &[u8] = b"SSHSIG";
+const PEM_HEADER: &str = "-----BEGIN SSH SIGNATURE-----";
+// 和 ssh-keygen -Y sign 一样默认使用 sha512
+const HASH_ALG: HashAlg = HashAlg::Sha512;
+
+pub struct SshsigSigner {
+    key: SigningKey,
+    namespace: String,
+}
+
+pub struct SshsigVerifier {
+    key: VerifyingKey,
+    namespace: String,
+}
+
+/// allowed_signers 文件中的一行，格式见 ssh-keygen(1) 的 ALLOWED SIGNERS 一节
+pub struct AllowedSigner {
+    pub principals: String,
+    pub namespaces: Option<String>,
+    pub valid_after: Option<SystemTime>,
+    pub valid_before: Option<SystemTime>,
+    pub cert_authority: bool,
+    pub key: PublicKey,
+}
+
+impl SshsigSigner {
+    pub fn try_new(key: impl AsRef<[u8]>, namespace: &str) -> Result<Self> {
+        Ok(Self {
+            key: decode_signing_key(key.as_ref())?,
+            namespace: check_namespace(namespace)?,
+        })
+    }
+}
+
+impl SshsigVerifier {
+    /// 支持 raw、PEM 和 OpenSSH 格式的 ed25519 公钥
+    pub fn try_new(key: impl AsRef<[u8]>, namespace: &str) -> Result<Self> {
+        Ok(Self {
+            key: decode_verifying_key(key.as_ref())?,
+            namespace: check_namespace(namespace)?,
+        })
+    }
+}
+
+impl TextSigner for SshsigSigner {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        let hash = hash_reader(reader, HASH_ALG)?;
+        let signature = self
+            .key
+            .sign(&signed_data(&self.namespace, &[], HASH_ALG, &hash));
+
+        let public = KeyData::Ed25519(Ed25519PublicKey::from(self.key.verifying_key()));
+        let signature = ssh_key::Signature::new(Algorithm::Ed25519, signature.to_bytes())?;
+        let sig = SshSig::new(public, &self.namespace, HASH_ALG, signature)?;
+        let mut content = sig.to_pem(LineEnding::LF)?;
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        Ok(content.into_bytes())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.verifying_key().as_bytes())
+    }
+}
+
+impl TextVerifier for SshsigVerifier {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        let sig = parse_sshsig(sig)?;
+        // namespace 用来区分用途，例如 git 的签名不能当作文件签名使用
+        if sig.namespace() != self.namespace {
+            anyhow::bail!(
+                "Signature was made for namespace {}, but expected {}",
+                sig.namespace(),
+                self.namespace
+            );
+        }
+        let public = sig_public_key(&sig)?;
+        if public != self.key {
+            anyhow::bail!(
+                "Signature was made with key {}, but the given key is {}",
+                key_fingerprint(public.as_bytes()),
+                key_fingerprint(self.key.as_bytes())
+            );
+        }
+
+        let hash = hash_reader(reader, sig.hash_alg())?;
+        let data = signed_data(sig.namespace(), sig.reserved(), sig.hash_alg(), &hash);
+        let Ok(signature) = Signature::try_from(sig.signature()) else {
+            return Ok(false);
+        };
+        Ok(self.key.verify(&data, &signature).is_ok())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.as_bytes())
+    }
+}
+
+impl AllowedSigner {
+    /// 和 ssh-keygen -Y verify 一样检查 principal、namespace 和有效期
+    pub fn allows(&self, principal: &str, namespace: &str, key: &KeyData, now: SystemTime) -> bool {
+        // 只支持普通公钥，cert-authority 需要 SSH 证书
+        !self.cert_authority
+            && self.key.key_data() == key
+            && match_pattern_list(principal, &self.principals)
+            && self
+                .namespaces
+                .as_deref()
+                .is_none_or(|namespaces| match_pattern_list(namespace, namespaces))
+            && self.valid_after.is_none_or(|t| now >= t)
+            && self.valid_before.is_none_or(|t| now <= t)
+    }
+
+    fn parse(line: &str) -> Result<Self> {
+        let fields = split_fields(line);
+        let (principals, rest) = fields
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("missing principals"))?;
+        // 选项是可选的，第二个字段是 key 类型时表示没有选项
+        let (options, key) = match rest.first() {
+            Some(field) if Algorithm::new(field).is_err() => (Some(*field), &rest[1..]),
+            _ => (None, rest),
+        };
+        if key.len() < 2 {
+            anyhow::bail!("missing public key");
+        }
+
+        let mut signer = Self {
+            principals: unquote(principals).to_string(),
+            namespaces: None,
+            valid_after: None,
+            valid_before: None,
+            cert_authority: false,
+            key: PublicKey::from_openssh(&key.join(" "))?,
+        };
+        for option in split_options(options.unwrap_or_default()) {
+            match option.split_once('=') {
+                None if option.eq_ignore_ascii_case("cert-authority") => {
+                    signer.cert_authority = true
+                }
+                Some((name, value)) if name.eq_ignore_ascii_case("namespaces") => {
+                    signer.namespaces = Some(unquote(value).to_string())
+                }
+                Some((name, value)) if name.eq_ignore_ascii_case("valid-after") => {
+                    signer.valid_after = Some(parse_time(unquote(value))?)
+                }
+                Some((name, value)) if name.eq_ignore_ascii_case("valid-before") => {
+                    signer.valid_before = Some(parse_time(unquote(value))?)
+                }
+                _ => anyhow::bail!("unsupported option {}", option),
+            }
+        }
+
+        Ok(signer)
+    }
+}
+
+/// 解析 allowed_signers 文件，忽略空行和注释
+pub fn parse_allowed_signers(content: &str) -> Result<Vec<AllowedSigner>> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            AllowedSigner::parse(line)
+                .map_err(|e| anyhow::anyhow!("Invalid allowed_signers line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// 在 allowed_signers 中查找允许 principal 在 namespace 下使用签名中公钥的记录，返回 OpenSSH 格式的公钥
+pub fn process_allowed_signers_key(
+    allowed_signers: &str,
+    principal: &str,
+    namespace: &str,
+    sig: &[u8],
+) -> Result<Vec<u8>> {
+    let sig = parse_sshsig(sig)?;
+    let now = SystemTime::now();
+    let signer = parse_allowed_signers(allowed_signers)?
+        .into_iter()
+        .find(|signer| signer.allows(principal, namespace, sig.public_key(), now))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No allowed signer matches principal {} and the signature's key in namespace {}",
+                principal,
+                namespace
+            )
+        })?;
+
+    Ok(format!("{}\n", signer.key.to_openssh()?).into_bytes())
+}
+
+pub fn process_sshsig_sign(reader: &mut dyn Read, key: &[u8], namespace: &str) -> Result<Vec<u8>> {
+    SshsigSigner::try_new(key, namespace)?.sign(reader)
+}
+
+pub fn process_sshsig_verify(
+    reader: &mut dyn Read,
+    key: &[u8],
+    sig: &[u8],
+    namespace: &str,
+) -> Result<bool> {
+    SshsigVerifier::try_new(key, namespace)?.verify(reader, sig)
+}
+
+pub fn is_sshsig_signature(content: &str) -> bool {
+    content.starts_with(PEM_HEADER)
+}
+
+fn check_namespace(namespace: &str) -> Result<String> {
+    if namespace.is_empty() {
+        anyhow::bail!("SSH signature namespace must not be empty");
+    }
+    Ok(namespace.to_string())
+}
+
+fn parse_sshsig(sig: &[u8]) -> Result<SshSig> {
+    SshSig::from_pem(sig).map_err(|e| anyhow::anyhow!("Invalid SSH signature: {}", e))
+}
+
+fn sig_public_key(sig: &SshSig) -> Result<VerifyingKey> {
+    match sig.public_key().ed25519() {
+        Some(key) => Ok(VerifyingKey::try_from(key)?),
+        None => anyhow::bail!(
+            "Unsupported SSH signature key type {}, only ed25519 is supported",
+            sig.public_key().algorithm()
+        ),
+    }
+}
+
+// 流式计算 hash，不需要把整个输入读到内存里
+fn hash_reader(reader: &mut dyn Read, hash_alg: HashAlg) -> Result<Vec<u8>> {
+    let hash = match hash_alg {
+        HashAlg::Sha256 => {
+            let mut hasher = Sha256::new();
+            io::copy(reader, &mut hasher)?;
+            hasher.finalize().to_vec()
+        }
+        HashAlg::Sha512 => {
+            let mut hasher = Sha512::new();
+            io::copy(reader, &mut hasher)?;
+            hasher.finalize().to_vec()
+        }
+        _ => anyhow::bail!("Unsupported SSH signature hash algorithm {}", hash_alg),
+    };
+    Ok(hash)
+}
+
+fn signed_data(namespace: &str, reserved: &[u8], hash_alg: HashAlg, hash: &[u8]) -> Vec<u8> {
+    let mut data = MAGIC_PREAMBLE.to_vec();
+    for field in [
+        namespace.as_bytes(),
+        reserved,
+        hash_alg.as_str().as_bytes(),
+        hash,
+    ] {
+        data.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        data.extend_from_slice(field);
+    }
+    data
+}
+
+// 按空白分割，双引号中的空白不分割
+fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if let Some(s) = start.take() {
+                    fields.push(&line[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        fields.push(&line[s..]);
+    }
+    fields
+}
+
+// 按逗号分割选项，双引号中的逗号不分割
+fn split_options(options: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in options.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                result.push(&options[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(&options[start..]);
+    result.into_iter().filter(|o| !o.is_empty()).collect()
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+// YYYYMMDD[HHMM[SS]]Z，OpenSSH 把不带 Z 的时间当作本地时间，这里只支持 UTC，避免和 ssh-keygen 的结果不一致
+fn parse_time(s: &str) -> Result<SystemTime> {
+    let Some(digits) = s.strip_suffix(['Z', 'z']) else {
+        anyhow::bail!(
+            "time {} must be UTC with a trailing Z; local times are not supported",
+            s
+        );
+    };
+    if !digits.chars().all(|c| c.is_ascii_digit()) || ![8, 12, 14].contains(&digits.len()) {
+        anyhow::bail!("invalid time {}", s);
+    }
+    let field = |range: std::ops::Range<usize>| digits.get(range).unwrap_or("00");
+    let rfc3339 = format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        field(0..4),
+        field(4..6),
+        field(6..8),
+        field(8..10),
+        field(10..12),
+        field(12..14)
+    );
+    humantime::parse_rfc3339(&rfc3339).map_err(|e| anyhow::anyhow!("invalid time {}: {}", s, e))
+}
+
+// OpenSSH 的 match_pattern_list：逗号分隔的 pattern，支持 * 和 ?，以 ! 开头的 pattern 匹配时直接拒绝
+fn match_pattern_list(s: &str, patterns: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        match pattern.strip_prefix('!') {
+            Some(pattern) if match_pattern(s.as_bytes(), pattern.as_bytes()) => return false,
+            Some(_) => {}
+            None => matched |= match_pattern(s.as_bytes(), pattern.as_bytes()),
+        }
+    }
+    matched
+}
+
+// 迭代匹配，失败时只回溯到最近的一个 *，最坏 O(len(s) * len(pattern))
+fn match_pattern(s: &[u8], pattern: &[u8]) -> bool {
+    let (mut si, mut pi) = (0, 0);
+    // 最近一个 * 在 pattern 中的位置，以及它当前匹配到的 s 的结束位置
+    let mut star = None;
+    while si < s.len() {
+        match pattern.get(pi) {
+            Some(b'*') => {
+                star = Some((pi, si));
+                pi += 1;
+            }
+            Some(&c) if c == b'?' || c == s[si] => {
+                si += 1;
+                pi += 1;
+            }
+            _ => match star {
+                Some((star_pi, star_si)) => {
+                    // 让 * 多吞一个字符再试
+                    star = Some((star_pi, star_si + 1));
+                    pi = star_pi + 1;
+                    si = star_si + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pi..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.signing_key");
+    const VERIFYING_KEY: &[u8] = include_bytes!("../../fixtures/ed25519.verifying_key");
+    const MESSAGE: &[u8] = include_bytes!("../../fixtures/hello.txt");
+    const SIGNATURE: &[u8] = include_bytes!("../../fixtures/hello.txt.sshsig");
+    const ALLOWED_SIGNERS: &str = include_str!("../../fixtures/allowed_signers");
+
+    #[test]
+    fn test_sshsig_sign_verify() -> Result<()> {
+        let signer = SshsigSigner::try_new(SIGNING_KEY, "file")?;
+        let sig = signer.sign(&mut &MESSAGE[..])?;
+        assert!(is_sshsig_signature(std::str::from_utf8(&sig)?));
+
+        let verifier = SshsigVerifier::try_new(VERIFYING_KEY, "file")?;
+        assert!(verifier.verify(&mut &MESSAGE[..], &sig)?);
+        assert!(!verifier.verify(&mut &b"hellO"[..], &sig)?);
+        assert_eq!(verifier.key_id(), signer.key_id());
+
+        // 由 ssh-keygen -Y sign -n file 生成的签名
+        assert!(verifier.verify(&mut &MESSAGE[..], SIGNATURE)?);
+
+        let git = SshsigVerifier::try_new(VERIFYING_KEY, "git")?;
+        assert!(git.verify(&mut &MESSAGE[..], &sig).is_err());
+        assert!(SshsigSigner::try_new(SIGNING_KEY, "").is_err());
+
+        // 通用的签名接口没有 namespace，不能悄悄使用默认值
+        let format = crate::TextSignFormat::Sshsig;
+        assert!(crate::process_text_sign(&mut &MESSAGE[..], SIGNING_KEY, format).is_err());
+        assert!(
+            crate::process_text_verify(&mut &MESSAGE[..], VERIFYING_KEY, &sig, format).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_allowed_signers() -> Result<()> {
+        let key =
+            process_allowed_signers_key(ALLOWED_SIGNERS, "alice@example.com", "file", SIGNATURE)?;
+        assert!(process_sshsig_verify(
+            &mut &MESSAGE[..],
+            &key,
+            SIGNATURE,
+            "file"
+        )?);
+
+        // principal 不匹配、被 ! 排除或者 namespace 不允许
+        for (principal, namespace) in [
+            ("bob@example.org", "file"),
+            ("mallory@example.com", "file"),
+            ("alice@example.com", "git"),
+        ] {
+            assert!(
+                process_allowed_signers_key(ALLOWED_SIGNERS, principal, namespace, SIGNATURE)
+                    .is_err()
+            );
+        }
+
+        assert!(match_pattern_list(
+            "ci@build.example.com",
+            "*@*.example.com"
+        ));
+        assert!(!match_pattern_list(
+            "a@example.org",
+            "*@example.com,b?@example.org"
+        ));
+        assert!(match_pattern_list("abc", "a*c,!x*"));
+        assert!(match_pattern_list("", "*"));
+        assert!(!match_pattern_list("ab", "a?b"));
+        // 病态 pattern 不能指数回溯
+        assert!(!match_pattern_list(
+            &"a".repeat(64),
+            "*a*a*a*a*a*a*a*a*a*a*b"
+        ));
+        assert!(parse_time("20240101Z")? < parse_time("202401011200Z")?);
+        assert!(parse_time("202401011200").is_err());
+        assert!(parse_allowed_signers("alice@example.com foo=bar ssh-ed25519 AAAA").is_err());
+        Ok(())
+    }
+}
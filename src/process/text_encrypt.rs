@@ -0,0 +1,494 @@
+use super::b64::read_full;
+use crate::{process_age_decrypt, TextCipher};
+use age::x25519::Identity;
+use anyhow::Result;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{
+        generic_array::GenericArray,
+        stream::{DecryptorBE32, EncryptorBE32},
+        Payload,
+    },
+    ChaCha20Poly1305, XChaCha20Poly1305,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::io::{Read, Write};
+
+// 文件格式：
+// magic(8) | version(1) | cipher(1) | kdf(1) | [salt(16) m_cost(4) t_cost(4) p_cost(4)]
+// | chunk_size(4) | nonce_prefix(7 或 19) | chunk...
+// header 整体作为每个 chunk 的 AAD，每个 chunk 带 16 字节的 tag，最后一个 chunk 使用 last 标记
+pub(crate) const MAGIC: &[u8; 8] = b"rcli-enc";
+const VERSION: u8 = 1;
+const CHUNK_SIZE: usize = 64 * 1024;
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+const TAG_SIZE: usize = 16;
+const SALT_SIZE: usize = 16;
+// header 中的 Argon2 参数不可信，限制内存（KiB）、迭代次数和并行度，避免构造的文件耗尽内存或者 CPU
+const MAX_M_COST: u32 = 1024 * 1024;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 8;
+// 和 blake3 签名使用同一个密钥文件时，派生出单独的加密密钥
+const KEY_CONTEXT: &str = "rcli text encrypt v1";
+
+/// 加密使用的密钥：blake3 密钥文件的内容，或者 passphrase
+pub enum EncryptionKey<'a> {
+    Key(&'a [u8]),
+    Passphrase(&'a str),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kdf {
+    Key,
+    Argon2id {
+        salt: [u8; SALT_SIZE],
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+}
+
+struct Header {
+    cipher: TextCipher,
+    kdf: Kdf,
+    chunk_size: usize,
+    nonce_prefix: Vec<u8>,
+}
+
+enum StreamEncryptor {
+    ChaCha20(EncryptorBE32<ChaCha20Poly1305>),
+    XChaCha20(EncryptorBE32<XChaCha20Poly1305>),
+}
+
+enum StreamDecryptor {
+    ChaCha20(DecryptorBE32<ChaCha20Poly1305>),
+    XChaCha20(DecryptorBE32<XChaCha20Poly1305>),
+}
+
+/// 流式加密，每次只在内存中保留两个 chunk
+pub fn process_text_encrypt(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    key: EncryptionKey,
+    cipher: TextCipher,
+) -> Result<()> {
+    let kdf = match key {
+        EncryptionKey::Key(_) => Kdf::Key,
+        EncryptionKey::Passphrase(_) => {
+            let mut salt = [0u8; SALT_SIZE];
+            OsRng.fill_bytes(&mut salt);
+            // OWASP 推荐的 Argon2id 参数：19 MiB 内存，2 次迭代，1 个并行度
+            Kdf::Argon2id {
+                salt,
+                m_cost: 19 * 1024,
+                t_cost: 2,
+                p_cost: 1,
+            }
+        }
+    };
+
+    let mut nonce_prefix = vec![0u8; nonce_prefix_size(cipher)];
+    OsRng.fill_bytes(&mut nonce_prefix);
+    let header = Header {
+        cipher,
+        kdf,
+        chunk_size: CHUNK_SIZE,
+        nonce_prefix,
+    };
+    let aad = header.to_bytes();
+    writer.write_all(&aad)?;
+
+    let key = derive_key(kdf, key)?;
+    let mut encryptor = StreamEncryptor::new(cipher, &key, &header.nonce_prefix);
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut next = vec![0u8; CHUNK_SIZE];
+    let mut n = read_full(reader, &mut buf)?;
+    loop {
+        // 需要多读一个 chunk 才能知道当前 chunk 是不是最后一个
+        let m = if n < CHUNK_SIZE {
+            0
+        } else {
+            read_full(reader, &mut next)?
+        };
+        if m == 0 {
+            writer.write_all(&encryptor.encrypt_last(&buf[..n], &aad)?)?;
+            break;
+        }
+
+        writer.write_all(&encryptor.encrypt_next(&buf[..n], &aad)?)?;
+        std::mem::swap(&mut buf, &mut next);
+        n = m;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// 根据 header 选择算法，使用 passphrase 加密的文件会调用 passphrase 获取密码
+/// 不是 rcli 格式的文件当作 age 文件，使用 identities 解密
+pub fn process_text_decrypt(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    key: Option<&[u8]>,
+    identities: &[Identity],
+    passphrase: impl FnOnce() -> Result<String>,
+) -> Result<()> {
+    let mut magic = [0u8; MAGIC.len()];
+    let n = read_full(reader, &mut magic)?;
+    let mut reader = (&magic[..n]).chain(reader);
+    if &magic[..n] != MAGIC {
+        return process_age_decrypt(&mut reader, writer, identities);
+    }
+
+    let (header, aad) = Header::read(&mut reader)?;
+    let key = match (header.kdf, key) {
+        (Kdf::Key, Some(key)) => derive_key(header.kdf, EncryptionKey::Key(key))?,
+        (Kdf::Key, None) => anyhow::bail!("This file was encrypted with a key file, use --key"),
+        (Kdf::Argon2id { .. }, _) => {
+            let passphrase = passphrase()?;
+            derive_key(header.kdf, EncryptionKey::Passphrase(&passphrase))?
+        }
+    };
+    let mut decryptor = StreamDecryptor::new(header.cipher, &key, &header.nonce_prefix);
+
+    let size = header.chunk_size + TAG_SIZE;
+    let mut buf = vec![0u8; size];
+    let mut next = vec![0u8; size];
+    let mut n = read_full(&mut reader, &mut buf)?;
+    loop {
+        let m = if n < size {
+            0
+        } else {
+            read_full(&mut reader, &mut next)?
+        };
+        if m == 0 {
+            // 被截断的文件在这里会因为 last 标记不匹配而失败
+            writer.write_all(&decryptor.decrypt_last(&buf[..n], &aad)?)?;
+            break;
+        }
+
+        writer.write_all(&decryptor.decrypt_next(&buf[..n], &aad)?)?;
+        std::mem::swap(&mut buf, &mut next);
+        n = m;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+impl Header {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(cipher_id(self.cipher));
+        match self.kdf {
+            Kdf::Key => bytes.push(0),
+            Kdf::Argon2id {
+                salt,
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&salt);
+                bytes.extend_from_slice(&m_cost.to_be_bytes());
+                bytes.extend_from_slice(&t_cost.to_be_bytes());
+                bytes.extend_from_slice(&p_cost.to_be_bytes());
+            }
+        }
+        bytes.extend_from_slice(&(self.chunk_size as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.nonce_prefix);
+        bytes
+    }
+
+    // 返回解析出的 header 和 header 的原始字节，原始字节用作 AAD
+    fn read(reader: &mut dyn Read) -> Result<(Self, Vec<u8>)> {
+        let mut raw = Vec::new();
+        let mut read = |len: usize| -> Result<Vec<u8>> {
+            let mut buf = vec![0u8; len];
+            reader
+                .read_exact(&mut buf)
+                .map_err(|_| anyhow::anyhow!("Invalid encrypted file: header is truncated"))?;
+            raw.extend_from_slice(&buf);
+            Ok(buf)
+        };
+
+        if read(MAGIC.len())? != MAGIC {
+            anyhow::bail!("Invalid encrypted file: not an rcli encrypted file");
+        }
+        let version = read(1)?[0];
+        if version != VERSION {
+            anyhow::bail!("Unsupported encrypted file version: {}", version);
+        }
+        let cipher = cipher_from_id(read(1)?[0])?;
+        let kdf = match read(1)?[0] {
+            0 => Kdf::Key,
+            1 => Kdf::Argon2id {
+                salt: read(SALT_SIZE)?
+                    .try_into()
+                    .expect("salt has SALT_SIZE bytes"),
+                m_cost: read_u32(&read(4)?),
+                t_cost: read_u32(&read(4)?),
+                p_cost: read_u32(&read(4)?),
+            },
+            kdf => anyhow::bail!("Unsupported key derivation in encrypted file: {}", kdf),
+        };
+        if let Kdf::Argon2id {
+            m_cost,
+            t_cost,
+            p_cost,
+            ..
+        } = kdf
+        {
+            if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+                anyhow::bail!(
+                    "Unsupported Argon2 parameters in encrypted file: m_cost={} t_cost={} p_cost={}",
+                    m_cost,
+                    t_cost,
+                    p_cost
+                );
+            }
+        }
+        let chunk_size = read_u32(&read(4)?) as usize;
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            anyhow::bail!(
+                "Invalid encrypted file: chunk size {} is out of range",
+                chunk_size
+            );
+        }
+        let nonce_prefix = read(nonce_prefix_size(cipher))?;
+
+        let header = Self {
+            cipher,
+            kdf,
+            chunk_size,
+            nonce_prefix,
+        };
+        Ok((header, raw))
+    }
+}
+
+impl StreamEncryptor {
+    fn new(cipher: TextCipher, key: &[u8; 32], nonce_prefix: &[u8]) -> Self {
+        let key = GenericArray::from_slice(key);
+        // 两种算法的 nonce prefix 长度不同，GenericArray 的类型也不同
+        match cipher {
+            TextCipher::ChaCha20Poly1305 => Self::ChaCha20(EncryptorBE32::new(
+                key,
+                GenericArray::from_slice(nonce_prefix),
+            )),
+            TextCipher::XChaCha20Poly1305 => Self::XChaCha20(EncryptorBE32::new(
+                key,
+                GenericArray::from_slice(nonce_prefix),
+            )),
+        }
+    }
+
+    fn encrypt_next(&mut self, msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload { msg, aad };
+        let ret = match self {
+            Self::ChaCha20(e) => e.encrypt_next(payload),
+            Self::XChaCha20(e) => e.encrypt_next(payload),
+        };
+        ret.map_err(|_| anyhow::anyhow!("Encryption failed"))
+    }
+
+    fn encrypt_last(self, msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload { msg, aad };
+        let ret = match self {
+            Self::ChaCha20(e) => e.encrypt_last(payload),
+            Self::XChaCha20(e) => e.encrypt_last(payload),
+        };
+        ret.map_err(|_| anyhow::anyhow!("Encryption failed"))
+    }
+}
+
+impl StreamDecryptor {
+    fn new(cipher: TextCipher, key: &[u8; 32], nonce_prefix: &[u8]) -> Self {
+        let key = GenericArray::from_slice(key);
+        match cipher {
+            TextCipher::ChaCha20Poly1305 => Self::ChaCha20(DecryptorBE32::new(
+                key,
+                GenericArray::from_slice(nonce_prefix),
+            )),
+            TextCipher::XChaCha20Poly1305 => Self::XChaCha20(DecryptorBE32::new(
+                key,
+                GenericArray::from_slice(nonce_prefix),
+            )),
+        }
+    }
+
+    fn decrypt_next(&mut self, msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload { msg, aad };
+        let ret = match self {
+            Self::ChaCha20(d) => d.decrypt_next(payload),
+            Self::XChaCha20(d) => d.decrypt_next(payload),
+        };
+        ret.map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or corrupted data"))
+    }
+
+    fn decrypt_last(self, msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload { msg, aad };
+        let ret = match self {
+            Self::ChaCha20(d) => d.decrypt_last(payload),
+            Self::XChaCha20(d) => d.decrypt_last(payload),
+        };
+        ret.map_err(|_| {
+            anyhow::anyhow!("Decryption failed: wrong key, corrupted or truncated data")
+        })
+    }
+}
+
+fn derive_key(kdf: Kdf, key: EncryptionKey) -> Result<[u8; 32]> {
+    match (kdf, key) {
+        (Kdf::Key, EncryptionKey::Key(key)) => {
+            let key: &[u8; 32] =
+                key.get(..32)
+                    .and_then(|k| k.try_into().ok())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Invalid encryption key: expected at least 32 bytes, got {}",
+                            key.len()
+                        )
+                    })?;
+            Ok(blake3::derive_key(KEY_CONTEXT, key))
+        }
+        (
+            Kdf::Argon2id {
+                salt,
+                m_cost,
+                t_cost,
+                p_cost,
+            },
+            EncryptionKey::Passphrase(passphrase),
+        ) => {
+            let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+                .map_err(|e| anyhow::anyhow!("Invalid argon2 parameters: {}", e))?;
+            let mut key = [0u8; 32];
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+                .map_err(|e| anyhow::anyhow!("Failed to derive key from passphrase: {}", e))?;
+            Ok(key)
+        }
+        _ => anyhow::bail!("Key type does not match the key derivation"),
+    }
+}
+
+fn cipher_id(cipher: TextCipher) -> u8 {
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => 1,
+        TextCipher::XChaCha20Poly1305 => 2,
+    }
+}
+
+fn cipher_from_id(id: u8) -> Result<TextCipher> {
+    match id {
+        1 => Ok(TextCipher::ChaCha20Poly1305),
+        2 => Ok(TextCipher::XChaCha20Poly1305),
+        _ => anyhow::bail!("Unsupported cipher in encrypted file: {}", id),
+    }
+}
+
+// STREAM 的 nonce 由 prefix、4 字节 counter 和 1 字节 last 标记组成
+fn nonce_prefix_size(cipher: TextCipher) -> usize {
+    match cipher {
+        TextCipher::ChaCha20Poly1305 => 12 - 5,
+        TextCipher::XChaCha20Poly1305 => 24 - 5,
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes.try_into().expect("read 4 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = include_bytes!("../../fixtures/blake3.txt");
+
+    fn encrypt(data: &[u8], key: EncryptionKey, cipher: TextCipher) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        process_text_encrypt(&mut &data[..], &mut out, key, cipher)?;
+        Ok(out)
+    }
+
+    fn decrypt(data: &[u8], key: Option<&[u8]>, passphrase: &str) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        process_text_decrypt(&mut &data[..], &mut out, key, &[], || {
+            Ok(passphrase.to_string())
+        })?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_with_key() -> Result<()> {
+        // 覆盖空输入、不足一个 chunk、正好一个 chunk 和多个 chunk 的情况
+        for len in [0, 5, CHUNK_SIZE, CHUNK_SIZE * 2 + 7] {
+            let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            for cipher in [TextCipher::ChaCha20Poly1305, TextCipher::XChaCha20Poly1305] {
+                let encrypted = encrypt(&data, EncryptionKey::Key(KEY), cipher)?;
+                assert_eq!(decrypt(&encrypted, Some(KEY), "")?, data);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_with_passphrase() -> Result<()> {
+        let data = b"hello world";
+        let encrypted = encrypt(
+            data,
+            EncryptionKey::Passphrase("secret"),
+            TextCipher::XChaCha20Poly1305,
+        )?;
+        assert_eq!(decrypt(&encrypted, None, "secret")?, data);
+        assert!(decrypt(&encrypted, None, "wrong").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampering() -> Result<()> {
+        let data = vec![7u8; CHUNK_SIZE * 2];
+        let encrypted = encrypt(&data, EncryptionKey::Key(KEY), TextCipher::ChaCha20Poly1305)?;
+
+        // 修改 header 中的 chunk 大小、修改密文、截断最后一个 chunk 都会失败
+        let mut tampered = encrypted.clone();
+        tampered[12] ^= 1;
+        assert!(decrypt(&tampered, Some(KEY), "").is_err());
+
+        let mut tampered = encrypted.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt(&tampered, Some(KEY), "").is_err());
+
+        let header_len = encrypted.len() - 2 * (CHUNK_SIZE + TAG_SIZE);
+        let truncated = &encrypted[..header_len + CHUNK_SIZE + TAG_SIZE];
+        assert!(decrypt(truncated, Some(KEY), "").is_err());
+
+        assert!(decrypt(&encrypted, None, "").is_err());
+        assert!(decrypt(b"not encrypted", Some(KEY), "").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_rejects_oversized_kdf_params() -> Result<()> {
+        let encrypted = encrypt(
+            b"hello",
+            EncryptionKey::Passphrase("secret"),
+            TextCipher::XChaCha20Poly1305,
+        )?;
+
+        // m_cost、t_cost、p_cost 在 salt 之后，分别设置为超过上限的值
+        let offset = MAGIC.len() + 3 + SALT_SIZE;
+        for (i, value) in [(0, MAX_M_COST + 1), (1, MAX_T_COST + 1), (2, u32::MAX)] {
+            let mut tampered = encrypted.clone();
+            let start = offset + i * 4;
+            tampered[start..start + 4].copy_from_slice(&value.to_be_bytes());
+            let err = decrypt(&tampered, None, "secret").unwrap_err();
+            assert!(err.to_string().contains("Unsupported Argon2 parameters"));
+        }
+        Ok(())
+    }
+}
@@ -1,23 +1,48 @@
-use crate::{process_genpass, TextSignFormat};
+use crate::{
+    decode_signing_key, decode_verifying_key, encode_signing_key, encode_verifying_key,
+    identity_to_string, process_genpass, process_protect_key, x25519_identity, x25519_recipient,
+    KeyFormat, MinisignSigner, MinisignVerifier, TextSignFormat,
+};
 use anyhow::Result;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hmac::{digest::KeyInit, Hmac, Mac};
 use rand::rngs::OsRng;
-use std::{collections::HashMap, io::Read};
+use sha2::{Digest, Sha256, Sha512};
+use std::{collections::HashMap, io, io::Read, path::Path};
 
 pub trait TextSigner {
     // signer could sign any input data
     fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>>;
+
+    // 和对应的 verifier 的 key_id 相同，用来在签名文件里标识密钥
+    fn key_id(&self) -> String;
 }
 
 pub trait TextVerifier {
     // verifier could verify any input data
     fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool>;
+
+    fn key_id(&self) -> String;
+}
+
+/// 密钥指纹：公钥的 blake3 hash 的前 8 个字节
+pub fn key_fingerprint(public: &[u8]) -> String {
+    blake3::hash(public).as_bytes()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
 }
 
 pub struct Blake3 {
     key: [u8; 32],
 }
 
+/// HMAC-SHA256 和 HMAC-SHA512，key 可以是任意长度，常用于 webhook 的签名
+pub struct HmacSha2 {
+    key: Vec<u8>,
+    format: TextSignFormat,
+}
+
 pub struct Ed25519Signer {
     key: SigningKey,
 }
@@ -26,29 +51,83 @@ pub struct Ed25519Verifier {
     key: VerifyingKey,
 }
 
-impl TextSigner for Blake3 {
-    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
-        let mut buf = Vec::new();
+/// Ed25519ph (RFC 8032)，先对输入做 SHA-512 再签名，输入可以流式读取
+pub struct Ed25519phSigner {
+    key: SigningKey,
+}
 
-        // 读取所有的 bytes，直到 EOF
-        reader.read_to_end(&mut buf)?;
+pub struct Ed25519phVerifier {
+    key: VerifyingKey,
+}
 
-        let hash = blake3::keyed_hash(&self.key, &buf);
+impl TextSigner for Blake3 {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        // 增量读取并 hash，不需要把整个输入读到内存里
+        let hash = blake3::Hasher::new_keyed(&self.key)
+            .update_reader(reader)?
+            .finalize();
 
         Ok(hash.as_bytes().to_vec())
     }
+
+    fn key_id(&self) -> String {
+        self.fingerprint()
+    }
 }
 
 impl TextVerifier for Blake3 {
     fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf)?;
-
         // 对原始数据再次进行 keyed hash，验证其结果和签名是否一致
         // 这个其实就是 哈希验证
-        let hash = blake3::keyed_hash(&self.key, &buf);
+        let hash = blake3::Hasher::new_keyed(&self.key)
+            .update_reader(reader)?
+            .finalize();
+
+        // blake3::Hash 和 [u8] 的比较是常量时间的
+        Ok(hash == *sig)
+    }
+
+    fn key_id(&self) -> String {
+        self.fingerprint()
+    }
+}
+
+impl TextSigner for HmacSha2 {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        let mac = match self.format {
+            TextSignFormat::HmacSha256 => hmac_reader::<Hmac<Sha256>>(&self.key, reader)?
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            _ => hmac_reader::<Hmac<Sha512>>(&self.key, reader)?
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+        };
+
+        Ok(mac)
+    }
+
+    fn key_id(&self) -> String {
+        self.fingerprint()
+    }
+}
+
+impl TextVerifier for HmacSha2 {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        // verify_slice 是常量时间的比较，长度不对时直接返回错误
+        let verified = match self.format {
+            TextSignFormat::HmacSha256 => {
+                hmac_reader::<Hmac<Sha256>>(&self.key, reader)?.verify_slice(sig)
+            }
+            _ => hmac_reader::<Hmac<Sha512>>(&self.key, reader)?.verify_slice(sig),
+        };
+
+        Ok(verified.is_ok())
+    }
 
-        Ok(hash.as_bytes() == sig)
+    fn key_id(&self) -> String {
+        self.fingerprint()
     }
 }
 
@@ -62,6 +141,10 @@ impl TextSigner for Ed25519Signer {
 
         Ok(signature.to_bytes().to_vec())
     }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.verifying_key().as_bytes())
+    }
 }
 
 impl TextVerifier for Ed25519Verifier {
@@ -69,19 +152,67 @@ impl TextVerifier for Ed25519Verifier {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
 
-        let sig = (&sig[..64]).try_into()?;
+        let Ok(sig) = sig.try_into() else {
+            return Ok(false);
+        };
         let signature = Signature::from_bytes(sig);
 
         // verify 方法是 Verifier trait 定义的方法，所以在使用时，需要将 trait import 进来
         Ok(self.key.verify(&buf, &signature).is_ok())
     }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.as_bytes())
+    }
+}
+
+impl TextSigner for Ed25519phSigner {
+    fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
+        let mut hasher = Sha512::new();
+        io::copy(reader, &mut hasher)?;
+
+        let signature = self.key.sign_prehashed(hasher, None)?;
+
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.verifying_key().as_bytes())
+    }
+}
+
+impl TextVerifier for Ed25519phVerifier {
+    fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
+        let Ok(sig) = sig.try_into() else {
+            return Ok(false);
+        };
+        let signature = Signature::from_bytes(sig);
+
+        let mut hasher = Sha512::new();
+        io::copy(reader, &mut hasher)?;
+
+        Ok(self.key.verify_prehashed(hasher, None, &signature).is_ok())
+    }
+
+    fn key_id(&self) -> String {
+        key_fingerprint(self.key.as_bytes())
+    }
 }
 
 impl Blake3 {
     pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
         let key = key.as_ref();
 
-        let key = (&key[..32]).try_into()?;
+        // 只取前 32 位，密钥文件末尾可能有换行
+        let key = key
+            .get(..32)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid blake3 key: expected at least 32 bytes, got {}",
+                    key.len()
+                )
+            })?
+            .try_into()?;
 
         Ok(Self::new(key))
     }
@@ -90,24 +221,98 @@ impl Blake3 {
         Self { key }
     }
 
-    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+    // 对称密钥不能直接 hash 后公开，先派生出一个专门用于 key id 的值
+    fn fingerprint(&self) -> String {
+        key_fingerprint(&blake3::derive_key("rcli blake3 key id", &self.key))
+    }
+
+    /// 通过 mmap 读取文件，并使用 rayon 多线程计算 keyed hash，适合很大的文件
+    pub fn hash_file(&self, path: &Path) -> Result<blake3::Hash> {
+        let hash = blake3::Hasher::new_keyed(&self.key)
+            .update_mmap_rayon(path)?
+            .finalize();
+
+        Ok(hash)
+    }
+
+    fn generate(
+        key_format: KeyFormat,
+        passphrase: Option<&str>,
+    ) -> Result<HashMap<&'static str, Vec<u8>>> {
+        if !matches!(key_format, KeyFormat::Raw) {
+            anyhow::bail!("blake3 keys only support the raw key format");
+        }
+
         // 使用 genpass 生成 32 位长的密码，作为 key
         let key = process_genpass(32, true, true, true, true)?;
 
         let mut map = HashMap::new();
-        map.insert("blake3.txt", key.as_bytes().to_vec());
+        map.insert("blake3.txt", protect(key.into_bytes(), passphrase)?);
 
         Ok(map)
     }
 }
 
-impl Ed25519Signer {
-    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        // 只取前 32 位
+impl HmacSha2 {
+    pub fn try_new(key: impl AsRef<[u8]>, format: TextSignFormat) -> Result<Self> {
+        if !matches!(
+            format,
+            TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512
+        ) {
+            anyhow::bail!("{} is not an HMAC format", format);
+        }
+
+        // 密钥文件末尾可能有换行，webhook 的 secret 通常是保存在文本文件里的
         let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
+        let key = key
+            .strip_suffix(b"\n")
+            .map(|k| k.strip_suffix(b"\r").unwrap_or(k))
+            .unwrap_or(key);
+        if key.is_empty() {
+            anyhow::bail!("Invalid HMAC key: the key is empty");
+        }
+
+        Ok(Self {
+            key: key.to_vec(),
+            format,
+        })
+    }
 
-        Ok(Self::new(key))
+    // 和 blake3 一样，不直接 hash 对称密钥
+    fn fingerprint(&self) -> String {
+        let key_id = blake3::derive_key("rcli hmac key id", &self.key);
+        key_fingerprint(&key_id)
+    }
+
+    fn generate(
+        key_format: KeyFormat,
+        passphrase: Option<&str>,
+    ) -> Result<HashMap<&'static str, Vec<u8>>> {
+        if !matches!(key_format, KeyFormat::Raw) {
+            anyhow::bail!("HMAC keys only support the raw key format");
+        }
+
+        let key = process_genpass(64, true, true, true, true)?;
+
+        let mut map = HashMap::new();
+        map.insert("hmac.txt", protect(key.into_bytes(), passphrase)?);
+
+        Ok(map)
+    }
+}
+
+// hmac 接受任意长度的 key，new_from_slice 不会失败
+fn hmac_reader<M: Mac + KeyInit + io::Write>(key: &[u8], reader: &mut dyn Read) -> Result<M> {
+    let mut mac = <M as KeyInit>::new_from_slice(key)?;
+    io::copy(reader, &mut mac)?;
+    Ok(mac)
+}
+
+impl Ed25519Signer {
+    /// 支持 raw、PKCS#8 PEM 和 OpenSSH 格式的私钥
+    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
+        let key = decode_signing_key(key.as_ref())?;
+        Ok(Self { key })
     }
 
     pub fn new(key: &[u8; 32]) -> Self {
@@ -115,7 +320,10 @@ impl Ed25519Signer {
         Self { key }
     }
 
-    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
+    fn generate(
+        key_format: KeyFormat,
+        passphrase: Option<&str>,
+    ) -> Result<HashMap<&'static str, Vec<u8>>> {
         let mut rng = OsRng;
         // 使用随机数生成私钥
         let signing_key = SigningKey::generate(&mut rng);
@@ -126,35 +334,105 @@ impl Ed25519Signer {
         // 因此可以支持转换
         let verifying_key: VerifyingKey = (&signing_key).into();
 
+        let (private_name, public_name) = match key_format {
+            KeyFormat::Raw => ("ed25519.signing_key", "ed25519.verifying_key"),
+            KeyFormat::Pem => ("ed25519.pem", "ed25519.pub.pem"),
+            KeyFormat::Openssh => ("id_ed25519", "id_ed25519.pub"),
+            KeyFormat::Minisign => {
+                anyhow::bail!(
+                    "minisign format is only supported for public keys, use text key convert"
+                )
+            }
+        };
+
         let mut map = HashMap::new();
-        map.insert("ed25519.signing_key", signing_key.to_bytes().to_vec());
-        map.insert("ed25519.verifying_key", verifying_key.to_bytes().to_vec());
+        map.insert(
+            private_name,
+            protect(encode_signing_key(&signing_key, key_format)?, passphrase)?,
+        );
+        map.insert(
+            public_name,
+            encode_verifying_key(&verifying_key, key_format)?,
+        );
+
+        // 同时生成对应的 age X25519 密钥，用于 text encrypt --recipient
+        let identity = x25519_identity(&signing_key)?;
+        let recipient = x25519_recipient(&verifying_key)?;
+        map.insert(
+            "x25519.identity",
+            protect(
+                format!("{}\n", identity_to_string(&identity)).into_bytes(),
+                passphrase,
+            )?,
+        );
+        map.insert("x25519.recipient", format!("{}\n", recipient).into_bytes());
 
         Ok(map)
     }
 }
 
-impl Ed25519Verifier {
+impl Ed25519phSigner {
     pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
+        let signer = Ed25519Signer::try_new(key)?;
+        Ok(Self { key: signer.key })
+    }
+}
 
-        let verifying_key = VerifyingKey::from_bytes(key)?;
-        Ok(Self { key: verifying_key })
+impl Ed25519phVerifier {
+    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
+        let verifier = Ed25519Verifier::try_new(key)?;
+        Ok(Self { key: verifier.key })
     }
 }
 
-pub fn process_text_sign(
-    reader: &mut dyn Read, // 需要签名的数据
-    key: &[u8],            // 签名使用的key
-    format: TextSignFormat,
-) -> Result<Vec<u8>> {
+impl Ed25519Verifier {
+    /// 支持 raw、PEM 和 OpenSSH 格式的公钥，也可以直接传入私钥
+    pub fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
+        let key = decode_verifying_key(key.as_ref())?;
+        Ok(Self { key })
+    }
+}
+
+// sshsig 没有默认的 namespace，必须通过 process_sshsig_sign/process_sshsig_verify 显式指定
+const SSHSIG_NAMESPACE_REQUIRED: &str =
+    "sshsig signatures need an explicit namespace, use process_sshsig_sign or process_sshsig_verify";
+
+fn signer(key: &[u8], format: TextSignFormat) -> Result<Box<dyn TextSigner>> {
     let signer: Box<dyn TextSigner> = match format {
         TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
         TextSignFormat::Ed25519 => Box::new(Ed25519Signer::try_new(key)?),
+        TextSignFormat::Ed25519ph => Box::new(Ed25519phSigner::try_new(key)?),
+        TextSignFormat::Minisign => Box::new(MinisignSigner::try_new(key, None)?),
+        TextSignFormat::Sshsig => anyhow::bail!(SSHSIG_NAMESPACE_REQUIRED),
+        TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => {
+            Box::new(HmacSha2::try_new(key, format)?)
+        }
+    };
+
+    Ok(signer)
+}
+
+fn verifier(key: &[u8], format: TextSignFormat) -> Result<Box<dyn TextVerifier>> {
+    let verifier: Box<dyn TextVerifier> = match format {
+        TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
+        TextSignFormat::Ed25519 => Box::new(Ed25519Verifier::try_new(key)?),
+        TextSignFormat::Ed25519ph => Box::new(Ed25519phVerifier::try_new(key)?),
+        TextSignFormat::Minisign => Box::new(MinisignVerifier::try_new(key)?),
+        TextSignFormat::Sshsig => anyhow::bail!(SSHSIG_NAMESPACE_REQUIRED),
+        TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => {
+            Box::new(HmacSha2::try_new(key, format)?)
+        }
     };
 
-    signer.sign(reader)
+    Ok(verifier)
+}
+
+pub fn process_text_sign(
+    reader: &mut dyn Read, // 需要签名的数据
+    key: &[u8],            // 签名使用的key
+    format: TextSignFormat,
+) -> Result<Vec<u8>> {
+    signer(key, format)?.sign(reader)
 }
 
 pub fn process_text_verify(
@@ -163,18 +441,59 @@ pub fn process_text_verify(
     sig: &[u8],
     format: TextSignFormat,
 ) -> Result<bool> {
-    let verifier: Box<dyn TextVerifier> = match format {
-        TextSignFormat::Blake3 => Box::new(Blake3::try_new(key)?),
-        TextSignFormat::Ed25519 => Box::new(Ed25519Verifier::try_new(key)?),
-    };
+    verifier(key, format)?.verify(reader, sig)
+}
+
+/// 签名使用的私钥和验证使用的公钥得到的 key id 相同
+pub fn process_signer_key_id(key: &[u8], format: TextSignFormat) -> Result<String> {
+    Ok(signer(key, format)?.key_id())
+}
 
-    verifier.verify(reader, sig)
+pub fn process_verifier_key_id(key: &[u8], format: TextSignFormat) -> Result<String> {
+    Ok(verifier(key, format)?.key_id())
 }
 
-pub fn process_text_key_generate(format: TextSignFormat) -> Result<HashMap<&'static str, Vec<u8>>> {
+/// 只支持 blake3，mmap 需要一个真实的文件而不是 stdin
+pub fn process_text_sign_mmap(path: &Path, key: &[u8]) -> Result<Vec<u8>> {
+    let hash = Blake3::try_new(key)?.hash_file(path)?;
+    Ok(hash.as_bytes().to_vec())
+}
+
+pub fn process_text_verify_mmap(path: &Path, key: &[u8], sig: &[u8]) -> Result<bool> {
+    let hash = Blake3::try_new(key)?.hash_file(path)?;
+    Ok(hash == *sig)
+}
+
+/// text generate 输出的公钥文件，其余文件都是私钥或对称密钥
+pub const PUBLIC_KEY_FILES: &[&str] = &[
+    "ed25519.verifying_key",
+    "ed25519.pub.pem",
+    "id_ed25519.pub",
+    "x25519.recipient",
+];
+
+pub fn process_text_key_generate(
+    format: TextSignFormat,
+    key_format: KeyFormat,
+    passphrase: Option<&str>,
+) -> Result<HashMap<&'static str, Vec<u8>>> {
     match format {
-        TextSignFormat::Blake3 => Blake3::generate(),
-        TextSignFormat::Ed25519 => Ed25519Signer::generate(),
+        TextSignFormat::Blake3 => Blake3::generate(key_format, passphrase),
+        TextSignFormat::HmacSha256 | TextSignFormat::HmacSha512 => {
+            HmacSha2::generate(key_format, passphrase)
+        }
+        TextSignFormat::Ed25519
+        | TextSignFormat::Ed25519ph
+        | TextSignFormat::Minisign
+        | TextSignFormat::Sshsig => Ed25519Signer::generate(key_format, passphrase),
+    }
+}
+
+// 指定 passphrase 时加密私钥文件，公钥仍然以明文保存
+fn protect(key: Vec<u8>, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    match passphrase {
+        Some(passphrase) => process_protect_key(&key, passphrase),
+        None => Ok(key),
     }
 }
 
@@ -219,6 +538,175 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_blake3_streaming_and_mmap() -> Result<()> {
+        // 超过 blake3 单次读取的 buffer，确保增量 hash 和一次性 hash 结果一致
+        let data = "hello".repeat(100_000);
+        let sig = process_text_sign(&mut data.as_bytes(), KEY, TextSignFormat::Blake3)?;
+        assert_eq!(
+            sig,
+            blake3::keyed_hash(&KEY[..32].try_into()?, data.as_bytes()).as_bytes()
+        );
+
+        let path = Path::new("fixtures/hello.txt");
+        let mut reader = File::open(path)?;
+        let sig = process_text_sign(&mut reader, KEY, TextSignFormat::Blake3)?;
+        assert_eq!(process_text_sign_mmap(path, KEY)?, sig);
+        assert!(process_text_verify_mmap(path, KEY, &sig)?);
+        assert!(!process_text_verify_mmap(path, KEY, &sig[..31])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_id() -> Result<()> {
+        let format = TextSignFormat::Ed25519;
+        let key_id = process_signer_key_id(SIGNING_KEY, format)?;
+        assert_eq!(key_id.len(), 16);
+        assert_eq!(key_id, process_verifier_key_id(VERIFY_KEY, format)?);
+
+        let format = TextSignFormat::Blake3;
+        assert_eq!(
+            process_signer_key_id(KEY, format)?,
+            process_verifier_key_id(KEY, format)?
+        );
+        assert_ne!(
+            process_signer_key_id(KEY, format)?,
+            key_fingerprint(&KEY[..32])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519_key_formats() -> Result<()> {
+        let format = TextSignFormat::Ed25519;
+        let sig = process_text_sign(&mut "hello".as_bytes(), SIGNING_KEY, format)?;
+
+        for key_format in [KeyFormat::Pem, KeyFormat::Openssh] {
+            let key = decode_signing_key(SIGNING_KEY)?;
+            let signing_key = encode_signing_key(&key, key_format)?;
+            let verifying_key = encode_verifying_key(&key.verifying_key(), key_format)?;
+
+            assert_eq!(
+                process_text_sign(&mut "hello".as_bytes(), &signing_key, format)?,
+                sig
+            );
+            assert!(process_text_verify(
+                &mut "hello".as_bytes(),
+                &verifying_key,
+                &sig,
+                format
+            )?);
+        }
+
+        // 生成的文件中只有 ed25519 公钥和 age recipient 是公钥
+        for key_format in [KeyFormat::Raw, KeyFormat::Pem, KeyFormat::Openssh] {
+            let keys = process_text_key_generate(format, key_format, None)?;
+            assert_eq!(keys.len(), 4);
+            assert_eq!(
+                keys.keys().filter(|k| PUBLIC_KEY_FILES.contains(k)).count(),
+                2
+            );
+        }
+
+        // 长度不对的密钥和签名返回错误，而不是 panic
+        assert!(process_text_sign(&mut "hello".as_bytes(), &SIGNING_KEY[..16], format).is_err());
+        assert!(
+            process_text_sign(&mut "hello".as_bytes(), b"short", TextSignFormat::Blake3).is_err()
+        );
+        assert!(!process_text_verify(
+            &mut "hello".as_bytes(),
+            VERIFY_KEY,
+            &sig[..32],
+            format
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hmac_sign_and_verify() -> Result<()> {
+        // RFC 4231 test case 2，key 末尾的换行会被去掉
+        let data = "what do ya want for nothing?";
+        for (format, expected) in [
+            (
+                TextSignFormat::HmacSha256,
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                TextSignFormat::HmacSha512,
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ] {
+            let sig = process_text_sign(&mut data.as_bytes(), b"Jefe\n", format)?;
+            assert_eq!(data_encoding::HEXLOWER.encode(&sig), expected);
+            assert!(process_text_verify(
+                &mut data.as_bytes(),
+                b"Jefe",
+                &sig,
+                format
+            )?);
+            assert!(!process_text_verify(
+                &mut data.as_bytes(),
+                b"jefe",
+                &sig,
+                format
+            )?);
+            assert!(!process_text_verify(
+                &mut data.as_bytes(),
+                b"Jefe",
+                &sig[..16],
+                format
+            )?);
+        }
+
+        // 长 key 会先被 hash，仍然可以使用
+        let key = [0xaa; 131];
+        let format = TextSignFormat::HmacSha256;
+        let sig = process_text_sign(&mut data.as_bytes(), &key, format)?;
+        assert!(process_text_verify(
+            &mut data.as_bytes(),
+            &key,
+            &sig,
+            format
+        )?);
+        assert!(process_text_sign(&mut data.as_bytes(), b"", format).is_err());
+        assert_ne!(
+            process_signer_key_id(b"Jefe", format)?,
+            key_fingerprint(b"Jefe")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519ph_sign_and_verify() -> Result<()> {
+        let data = "hello".repeat(100_000);
+        let format = TextSignFormat::Ed25519ph;
+
+        let sig = process_text_sign(&mut data.as_bytes(), SIGNING_KEY, format)?;
+        assert!(process_text_verify(
+            &mut data.as_bytes(),
+            VERIFY_KEY,
+            &sig,
+            format
+        )?);
+        assert!(!process_text_verify(
+            &mut "hello".as_bytes(),
+            VERIFY_KEY,
+            &sig,
+            format
+        )?);
+
+        // prehash 模式的签名和普通 ed25519 的签名不能互相验证
+        let ret = process_text_verify(
+            &mut data.as_bytes(),
+            VERIFY_KEY,
+            &sig,
+            TextSignFormat::Ed25519,
+        )?;
+        assert!(!ret);
+        Ok(())
+    }
+
     #[test]
     fn test_ed25519_sign_and_verify() -> Result<()> {
         // let mut reader = get_reader("fixtures/ed25519.signing_key")?;
@@ -0,0 +1,141 @@
+use crate::{process_genpass, TokenChecksum};
+use anyhow::Result;
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+// 62^6 > 2^32，6 个 base62 字符足够表示 32 位的 checksum
+const CHECKSUM_LEN: usize = 6;
+// body 使用 genpass 去掉 0/O/I/l 等易混淆字符后的 58 个字符，16 个字符最多约 93.7 位随机性
+// （每类字符至少出现一次，实际略少），更短的 token 可以被暴力猜测
+const MIN_BODY_LEN: u8 = 16;
+
+/// 生成形如 `prefix_<body><checksum>` 的 token
+/// body 由 genpass 生成（不含符号），checksum 基于 `prefix_<body>` 计算
+pub fn process_token_generate(prefix: &str, length: u8, checksum: TokenChecksum) -> Result<String> {
+    verify_prefix(prefix)?;
+    if length < MIN_BODY_LEN {
+        anyhow::bail!(
+            "token length must be at least {}, got {}",
+            MIN_BODY_LEN,
+            length
+        );
+    }
+
+    let body = process_genpass(length, true, true, true, false)?;
+    let payload = format!("{}_{}", prefix, body);
+    let checksum = encode_base62(checksum_of(&payload, checksum));
+
+    Ok(format!("{}{}", payload, checksum))
+}
+
+/// 离线校验 token 的格式和 checksum，在查库之前就可以拒绝伪造或者写错的 token
+pub fn process_token_verify(
+    token: &str,
+    prefix: Option<&str>,
+    checksum: TokenChecksum,
+) -> Result<()> {
+    let (token_prefix, rest) = token
+        .rsplit_once('_')
+        .ok_or_else(|| anyhow::anyhow!("missing prefix separator '_'"))?;
+    verify_prefix(token_prefix)?;
+
+    if let Some(prefix) = prefix {
+        anyhow::ensure!(
+            token_prefix == prefix,
+            "expected prefix {}, got {}",
+            prefix,
+            token_prefix
+        );
+    }
+
+    anyhow::ensure!(
+        rest.len() > CHECKSUM_LEN && rest.bytes().all(|b| b.is_ascii_alphanumeric()),
+        "body must be alphanumeric and longer than the {} character checksum",
+        CHECKSUM_LEN
+    );
+
+    let (body, actual) = rest.split_at(rest.len() - CHECKSUM_LEN);
+    let expected = encode_base62(checksum_of(&format!("{}_{}", token_prefix, body), checksum));
+    anyhow::ensure!(actual == expected, "{} checksum mismatch", checksum);
+
+    Ok(())
+}
+
+/// 只存储 token 的 blake3 hash，而不是 token 本身
+pub fn process_token_hash(token: &str) -> String {
+    blake3::hash(token.as_bytes()).to_hex().to_string()
+}
+
+fn checksum_of(payload: &str, checksum: TokenChecksum) -> u32 {
+    match checksum {
+        TokenChecksum::Crc32 => crc32fast::hash(payload.as_bytes()),
+        TokenChecksum::Blake3 => {
+            let hash = blake3::hash(payload.as_bytes());
+            let bytes = hash.as_bytes();
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        }
+    }
+}
+
+fn encode_base62(mut n: u32) -> String {
+    let mut buf = [BASE62[0]; CHECKSUM_LEN];
+    for c in buf.iter_mut().rev() {
+        *c = BASE62[(n % 62) as usize];
+        n /= 62;
+    }
+
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+fn verify_prefix(prefix: &str) -> Result<()> {
+    anyhow::ensure!(
+        !prefix.is_empty() && prefix.bytes().all(|b| b.is_ascii_alphanumeric()),
+        "prefix must be non-empty and alphanumeric"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_generate_and_verify() -> Result<()> {
+        for checksum in [TokenChecksum::Crc32, TokenChecksum::Blake3] {
+            let token = process_token_generate("rcli", 30, checksum)?;
+            assert!(token.starts_with("rcli_"));
+            assert_eq!(token.len(), "rcli_".len() + 30 + CHECKSUM_LEN);
+
+            process_token_verify(&token, Some("rcli"), checksum)?;
+            assert!(process_token_verify(&token, Some("ghp"), checksum).is_err());
+
+            // 修改 body 中的一个字符，checksum 就会失败
+            let mut tampered = token.into_bytes();
+            tampered[6] = if tampered[6] == b'a' { b'b' } else { b'a' };
+            let tampered = String::from_utf8(tampered)?;
+            assert!(process_token_verify(&tampered, None, checksum).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_length() -> Result<()> {
+        let token = process_token_generate("rcli", MIN_BODY_LEN, TokenChecksum::Crc32)?;
+        assert_eq!(
+            token.len(),
+            "rcli_".len() + MIN_BODY_LEN as usize + CHECKSUM_LEN
+        );
+        process_token_verify(&token, Some("rcli"), TokenChecksum::Crc32)?;
+
+        for length in [0, 2, MIN_BODY_LEN - 1] {
+            assert!(process_token_generate("rcli", length, TokenChecksum::Crc32).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_base62() {
+        assert_eq!(encode_base62(0), "000000");
+        assert_eq!(encode_base62(61), "00000z");
+        assert_eq!(encode_base62(u32::MAX), "4gfFC3");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::UrlMode;
+use anyhow::Result;
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::io::{Read, Write};
+
+/// RFC 3986 unreserved 字符之外的都需要编码，适用于 query string 中的 key 和 value
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// path segment 中还可以保留 sub-delims、':' 和 '@'，但是 '/' 需要编码
+const PATH_SEGMENT: &AsciiSet = &COMPONENT
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=')
+    .remove(b':')
+    .remove(b'@');
+
+/// application/x-www-form-urlencoded，空格编码为 '+'
+const FORM: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'*')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b' ');
+
+pub fn process_url_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    mode: UrlMode,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    let encoded = match mode {
+        UrlMode::Query => percent_encode(&buf, COMPONENT).to_string(),
+        UrlMode::Path => percent_encode(&buf, PATH_SEGMENT).to_string(),
+        UrlMode::Form => percent_encode(&buf, FORM).to_string().replace(' ', "+"),
+    };
+
+    writer.write_all(encoded.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn process_url_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    mode: UrlMode,
+) -> Result<()> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    if let UrlMode::Form = mode {
+        for b in buf.iter_mut().filter(|b| **b == b'+') {
+            *b = b' ';
+        }
+    }
+
+    writer.write_all(&percent_decode(&buf).collect::<Vec<_>>())?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(input: &str, mode: UrlMode) -> Result<String> {
+        let mut out = Vec::new();
+        process_url_encode(&mut input.as_bytes(), &mut out, mode)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn decode(input: &str, mode: UrlMode) -> Result<String> {
+        let mut out = Vec::new();
+        process_url_decode(&mut input.as_bytes(), &mut out, mode)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_url_encode_decode() -> Result<()> {
+        let input = "a b&c=d/e:f~g*h+中";
+
+        assert_eq!(
+            encode(input, UrlMode::Query)?,
+            "a%20b%26c%3Dd%2Fe%3Af~g%2Ah%2B%E4%B8%AD"
+        );
+        assert_eq!(
+            encode(input, UrlMode::Path)?,
+            "a%20b&c=d%2Fe:f~g*h+%E4%B8%AD"
+        );
+        assert_eq!(
+            encode(input, UrlMode::Form)?,
+            "a+b%26c%3Dd%2Fe%3Af%7Eg*h%2B%E4%B8%AD"
+        );
+
+        for mode in [UrlMode::Query, UrlMode::Path, UrlMode::Form] {
+            assert_eq!(decode(&encode(input, mode)?, mode)?, input);
+        }
+        Ok(())
+    }
+}
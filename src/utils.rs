@@ -1,6 +1,8 @@
+use crate::process_unprotect_key;
 use anyhow::Result;
-use std::fs::File;
-use std::io::Read;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     let reader: Box<dyn Read> = if input == "-" {
@@ -12,6 +14,65 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     Ok(reader)
 }
 
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(std::io::stdout())
+    } else {
+        Box::new(BufWriter::new(File::create(output)?))
+    };
+
+    Ok(writer)
+}
+
+/// 优先读取 RCLI_PASSPHRASE 环境变量，否则在终端提示输入，confirm 为 true 时需要输入两次
+pub fn read_passphrase(confirm: bool) -> Result<String> {
+    read_passphrase_from("RCLI_PASSPHRASE", "Passphrase: ", confirm)
+}
+
+/// 依次尝试环境变量 `<env>`、`<env>_FD` 指定的文件描述符（读取第一行）和终端输入
+pub fn read_passphrase_from(env: &str, prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(env) {
+        return Ok(passphrase);
+    }
+    if let Ok(fd) = std::env::var(format!("{}_FD", env)) {
+        let fd: u32 = fd
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid {}_FD: {}", env, fd))?;
+        let content = std::fs::read_to_string(format!("/dev/fd/{}", fd))?;
+        let passphrase = content.lines().next().unwrap_or_default();
+        if passphrase.is_empty() {
+            anyhow::bail!("Passphrase read from fd {} is empty", fd);
+        }
+        return Ok(passphrase.to_string());
+    }
+
+    let passphrase = rpassword::prompt_password(prompt)?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase must not be empty");
+    }
+    if confirm && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        anyhow::bail!("Passphrases do not match");
+    }
+
+    Ok(passphrase)
+}
+
+/// 读取密钥文件，使用 passphrase 保护的私钥会先解密
+pub fn get_key(input: &str) -> Result<Vec<u8>> {
+    unlock_key(&get_content(input)?, input)
+}
+
+/// 被保护的私钥会提示输入 passphrase，name 用于提示是哪个密钥
+pub fn unlock_key(content: &[u8], name: &str) -> Result<Vec<u8>> {
+    process_unprotect_key(content, || {
+        read_passphrase_from(
+            "RCLI_PASSPHRASE",
+            &format!("Passphrase for {}: ", name),
+            false,
+        )
+    })
+}
+
 pub fn get_content(input: &str) -> Result<Vec<u8>> {
     let mut reader = get_reader(input)?;
     let mut buf = Vec::new();
@@ -19,3 +80,32 @@ pub fn get_content(input: &str) -> Result<Vec<u8>> {
 
     Ok(buf)
 }
+
+/// 写入私钥文件：先以 0600 权限创建同目录下的临时文件，写完后 rename 到目标路径
+/// 私钥在任何时候都不会以默认权限存在，也可以安全地覆盖已有的文件
+pub fn write_private_file(path: &Path, content: &[u8]) -> Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid key file path: {}", path.display()))?;
+    let tmp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    // 上次中断时可能留下的临时文件
+    let _ = fs::remove_file(&tmp);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    let written = file
+        .write_all(content)
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(written?)
+}